@@ -1,36 +1,229 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use fuzzer_compare::{
+    campaign::Retention,
+    constants::{FUZZERS_CONFIG, TARGETS_CONFIG},
+    fuzzer::FuzzersConfig,
+    monitor::SinkKind,
+    target::TargetsConfig,
+    RunOptions,
+};
 
 /// A Cli for comparing fuzzers.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Setup all fuzzers and databases.
-    #[arg(short, long, default_value_t = false)]
-    setup: bool,
+    /// Directory holding `config/` and every build, test and output directory.
+    #[arg(short, long, global = true, value_name = "DIR")]
+    workspace: Option<String>,
 
-    /// Launch fuzzers in parallel.
-    #[arg(short, long, default_value_t = false)]
-    run: bool,
+    /// Stream build and run output to the terminal besides the log files.
+    #[arg(long, global = true, default_value_t = false)]
+    stream: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Download all sources into the local cache, see `$FUZZER_COMPARE_CACHE`.
+    Fetch {
+        /// Record checksums of fetched sources missing from the manifest.
+        #[arg(long, default_value_t = false)]
+        pin: bool,
+    },
+
+    /// Build fuzzers and databases, all of them unless some are named.
+    Setup {
+        /// Targets or fuzzers to build, e.g. `postgresql sqlsmith`.
+        names: Vec<String>,
+
+        /// Rebuild every step of a target or fuzzer, or of everything with `all`.
+        #[arg(long = "force", value_name = "TARGET")]
+        force: Vec<String>,
+    },
+
+    /// Start a new run of an experiment, queueing its campaigns within the core budget.
+    Run {
+        /// Name of the experiment, the run is stored under `runs/<TIMESTAMP>-<EXPERIMENT>`.
+        experiment: String,
+
+        /// Campaigns per fuzzer and target.
+        #[arg(long, default_value_t = 1)]
+        trials: u32,
+
+        /// Time budget of every campaign, e.g. `90s`, `30m` or `24h`, required
+        /// when the campaigns do not all fit the core budget at once.
+        #[arg(short, long, value_parser = parse_duration)]
+        duration: Option<std::time::Duration>,
+
+        /// Targets to fuzz, all by default.
+        #[arg(long, value_delimiter = ',')]
+        targets: Vec<String>,
+
+        /// Fuzzers to run, all by default.
+        #[arg(long, value_delimiter = ',')]
+        fuzzers: Vec<String>,
+
+        /// Fuzz another configured version of a target, e.g. `postgresql=16.1`.
+        #[arg(short = 't', long = "target-version", value_name = "TARGET=VERSION")]
+        target_versions: Vec<String>,
+
+        /// Also write every sample to `jsonl`, a `monitor.jsonl` per campaign,
+        /// or `sqlite`, the history database `runs/history.sqlite`.
+        #[arg(long, value_delimiter = ',')]
+        sinks: Vec<SinkKind>,
+
+        /// Serve the latest samples on `http://<ADDR>/metrics` for Prometheus,
+        /// e.g. `127.0.0.1:9184`; resuming the run serves them again.
+        #[arg(long, value_name = "ADDR")]
+        metrics: Option<String>,
+    },
+
+    /// Continue the queued and interrupted campaigns of a stopped run.
+    Resume {
+        /// Run, `latest`, or an experiment standing for its newest run.
+        run: String,
+    },
+
+    /// Show the progress of a run, or of the running runs.
+    Status {
+        /// Run, `latest`, or an experiment standing for its newest run.
+        run: Option<String>,
+
+        /// Keep redrawing the table until interrupted.
+        #[arg(long, default_value_t = false)]
+        watch: bool,
+
+        /// Refresh interval of `--watch`, e.g. `5s`.
+        #[arg(long, default_value = "5s", value_parser = parse_duration, requires = "watch")]
+        interval: std::time::Duration,
+    },
+
+    /// Browse runs, live charts, manifests, reports and triaged bugs in a web browser.
+    Serve {
+        /// Address to listen on, only reachable locally by default.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+
+    /// Stop every campaign of a run.
+    Stop { run: String },
+
+    /// Summarize the final sample of every campaign of a run.
+    Report { run: String },
+
+    /// Collect the unique crashes of a run.
+    Triage { run: String },
+
+    /// Remove a run, or the runs outside a retention policy.
+    Clean {
+        /// Run, `latest`, or an experiment standing for its newest run.
+        #[arg(conflicts_with_all = ["all", "keep_last", "older_than", "experiment"])]
+        run: Option<String>,
+
+        /// Remove every run.
+        #[arg(long, default_value_t = false, conflicts_with_all = ["keep_last", "older_than"])]
+        all: bool,
+
+        /// Keep only the newest N runs of each experiment.
+        #[arg(long, value_name = "N")]
+        keep_last: Option<usize>,
+
+        /// Remove runs started longer ago than this, e.g. `30d`.
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        older_than: Option<std::time::Duration>,
+
+        /// Only apply the policy to the runs of this experiment.
+        #[arg(long)]
+        experiment: Option<String>,
+    },
+}
+
+fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    fuzzer_compare::utils::parse_duration(s).map_err(|e| e.to_string())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
-    if args.setup {
-        fuzzer_compare::setup()?;
-    } else if args.run {
-        launch();
-    } else {
-        println!("Unknown arguments");
+    // Every path is resolved against the working directory on first use.
+    if let Some(workspace) = &args.workspace {
+        std::env::set_current_dir(workspace)?;
     }
+    fuzzer_compare::utils::set_live_log(args.stream);
 
-    Ok(())
-}
-
-fn launch() {
-    fuzzer_compare::launch_monitors();
+    let mut targets = TargetsConfig::load(TARGETS_CONFIG.as_str())?;
+    let fuzzers = FuzzersConfig::load(FUZZERS_CONFIG.as_str())?;
 
-    let handles = fuzzer_compare::launch_fuzzers();
-    for i in handles {
-        i.join().unwrap();
+    match args.command {
+        Command::Fetch { pin } => fuzzer_compare::fetch(&targets, &fuzzers, pin)?,
+        Command::Setup { names, force } => {
+            for target in &force {
+                if fuzzers.get(target).is_some() {
+                    targets.force_fuzzer(target);
+                } else {
+                    targets.force(target)?;
+                }
+            }
+            fuzzer_compare::setup(&targets, &fuzzers, &names)?;
+        }
+        Command::Run {
+            experiment,
+            trials,
+            duration,
+            targets: run_targets,
+            fuzzers: run_fuzzers,
+            target_versions,
+            sinks,
+            metrics,
+        } => {
+            for item in &target_versions {
+                let (target, version) = item
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected TARGET=VERSION, got {}", item))?;
+                targets.select(target, version)?;
+            }
+            let options = RunOptions {
+                trials,
+                duration,
+                targets: run_targets,
+                fuzzers: run_fuzzers,
+                sinks,
+                metrics,
+            };
+            fuzzer_compare::run(&experiment, &targets, &fuzzers, &options)?;
+        }
+        Command::Resume { run } => fuzzer_compare::resume(&run, &fuzzers)?,
+        Command::Status {
+            run,
+            watch,
+            interval,
+        } => fuzzer_compare::status(run.as_deref(), &fuzzers, watch.then_some(interval))?,
+        Command::Serve { addr } => fuzzer_compare::serve(&addr)?,
+        Command::Stop { run } => fuzzer_compare::stop(&run)?,
+        Command::Report { run } => fuzzer_compare::report(&run)?,
+        Command::Triage { run } => fuzzer_compare::triage(&run)?,
+        Command::Clean {
+            run,
+            all,
+            keep_last,
+            older_than,
+            experiment,
+        } => {
+            let retention = Retention {
+                keep_last: if all { Some(0) } else { keep_last },
+                older_than,
+                experiment,
+            };
+            // Results are only ever removed on explicit request.
+            if run.is_none() && retention.keep_last.is_none() && retention.older_than.is_none() {
+                return Err(
+                    "Name a run to clean, or pass --all, --keep-last or --older-than".into(),
+                );
+            }
+            fuzzer_compare::clean(run.as_deref(), &retention)?;
+        }
     }
+
+    Ok(())
 }
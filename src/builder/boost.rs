@@ -1,17 +1,17 @@
 use crate::{
-    constants::{BOOST_VERSION, BOOST_VERSION_1, DB_DIR},
-    utils::ShellCommand,
+    source,
+    target::{self, TargetsConfig},
+    utils,
 };
 
-use super::Builder;
+use super::{Builder, Stamp};
 
 pub struct BoostBuilder;
 
 impl Builder for BoostBuilder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>> {
-        let boost_src_dir = format!("{}/boost", DB_DIR.as_str());
-        if !std::path::Path::new(&boost_src_dir).exists() {
-            Self::download()?;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        for version in targets.versions("boost") {
+            Self::download(&version, targets.forced("boost"))?;
         }
 
         Ok(())
@@ -19,41 +19,20 @@ impl Builder for BoostBuilder {
 }
 
 impl BoostBuilder {
-    fn download() -> Result<(), Box<dyn std::error::Error>> {
-        let file = format!("boost_{}.tar.bz2", BOOST_VERSION_1);
-        let target = format!("{}/{}", DB_DIR.as_str(), file);
-
-        if !std::path::Path::new(&target).exists() {
-            let download_cmd = [
-                "wget".to_string(),
-                format!(
-                    "https://boostorg.jfrog.io/artifactory/main/release/{}/source/{}",
-                    BOOST_VERSION, file
-                ),
-            ];
-
-            ShellCommand::new()
-                .args(&download_cmd)
-                .current_dir(DB_DIR.as_str())
-                .spawn()?
-                .wait_with_output()?;
-        }
-
-        let extract_cmd = ["tar", "xvf", &target];
-        ShellCommand::new()
-            .args(&extract_cmd)
-            .current_dir(DB_DIR.as_str())
-            .spawn()?
-            .wait_with_output()?;
-
-        let raw_src_dir = format!("boost_{}", BOOST_VERSION_1);
-        let other_cmd = ["mv", &raw_src_dir, "boost"];
-        ShellCommand::new()
-            .args(&other_cmd)
-            .current_dir(DB_DIR.as_str())
-            .spawn()?
-            .wait_with_output()?;
-
-        Ok(())
+    fn download(version: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let boost_src_dir = target::src_dir("boost", version);
+
+        let source = source::get("boost", version)?;
+        let source_stamp = Stamp::new(
+            &format!("boost-{}", version),
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&boost_src_dir).exists() {
+                utils::remove_dir(&boost_src_dir)?;
+            }
+            source.checkout(&boost_src_dir)
+        })
     }
 }
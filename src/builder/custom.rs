@@ -0,0 +1,53 @@
+use crate::{
+    fuzzer::{self, FuzzerConfig},
+    utils::{self, ShellCommand},
+};
+
+use super::Stamp;
+
+/// Builds a fuzzer defined in `config/fuzzers.toml` with its own commands
+pub struct CustomBuilder;
+
+impl CustomBuilder {
+    pub fn setup(
+        name: &str,
+        config: &FuzzerConfig,
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let fuzzer_dir = fuzzer::fuzzer_dir(name);
+
+        let mut prev_hash = String::new();
+        if let Some(source) = config.source(name) {
+            let source_stamp = Stamp::new(
+                name,
+                "source",
+                &[source.key(), source.pinned().unwrap_or_default()],
+            );
+            source_stamp.run(force, || {
+                if std::path::Path::new(&fuzzer_dir).exists() {
+                    utils::remove_dir(&fuzzer_dir)?;
+                }
+                source.checkout(&fuzzer_dir)
+            })?;
+            prev_hash = source_stamp.hash().to_string();
+        } else {
+            utils::create_dir(&fuzzer_dir)?;
+        }
+
+        // Each command is a step of its own, so a failing one resumes where it stopped.
+        for (i, build_cmd) in config.build.iter().enumerate() {
+            let step = format!("build-{}", i);
+            let build_stamp = Stamp::new(name, &step, &[prev_hash.as_str(), build_cmd]);
+            build_stamp.run(force, || {
+                ShellCommand::new()
+                    .args(&[build_cmd])
+                    .current_dir(&fuzzer_dir)
+                    .log_to(utils::log_file("build", name, &step))
+                    .run()
+            })?;
+            prev_hash = build_stamp.hash().to_string();
+        }
+
+        Ok(())
+    }
+}
@@ -1,65 +1,106 @@
 use crate::{
-    constants::{CFLAGS, DB_DIR, FUZZERS_DIR, INSTALL_DIR, MARIADB_VERSION},
-    utils::{self, ShellCommand},
+    constants::{CFLAGS, FUZZERS_DIR},
+    source,
+    target::{self, TargetsConfig},
+    utils,
 };
 
-use super::Builder;
+use super::{stamp, Builder, Stamp};
 
 pub struct MariadbBuilder;
 
 impl Builder for MariadbBuilder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>> {
-        Self::build_with_squirrel()?;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let force = targets.forced("mariadb");
+        for version in targets.versions("mariadb") {
+            Self::build_with_squirrel(&version, force)?;
+        }
 
         Ok(())
     }
 }
 
 impl MariadbBuilder {
-    fn download() -> Result<(), Box<dyn std::error::Error>> {
-        let ma_src_dir = format!("{}/mariadb", DB_DIR.as_str());
-        utils::git_clone("https://github.com/MariaDB/server.git", Some(&ma_src_dir))?;
+    fn download(version: &str, force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
+        let ma_src_dir = target::src_dir("mariadb", version);
 
-        let cmd = ["git", "checkout", MARIADB_VERSION];
-        ShellCommand::new()
-            .args(&cmd)
-            .current_dir(&ma_src_dir)
-            .spawn()?
-            .wait_with_output()?;
+        let source = source::get("mariadb", version)?;
+        let source_stamp = Stamp::new(
+            &format!("mariadb-{}", version),
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&ma_src_dir).exists() {
+                utils::remove_dir(&ma_src_dir)?;
+            }
+            source.checkout(&ma_src_dir)
+        })?;
 
-        Ok(())
+        Ok(source_stamp)
     }
 
-    fn build_with_squirrel() -> Result<(), Box<dyn std::error::Error>> {
-        let ma_install_dir = format!("{}/mariadb", INSTALL_DIR.as_str());
-        if std::path::Path::new(&ma_install_dir).exists() {
-            return Ok(());
-        }
-
-        let ma_src_dir = format!("{}/mariadb", DB_DIR.as_str());
-        if !std::path::Path::new(&ma_src_dir).exists() {
-            Self::download()?;
-        }
+    fn build_with_squirrel(version: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let stamp_target = format!("mariadb-{}", version);
+        let ma_install_dir = target::install_dir("mariadb", version);
+        let ma_src_dir = target::src_dir("mariadb", version);
+        let source_stamp = Self::download(version, force)?;
 
         let ma_bin_dir = format!("{}/build", ma_src_dir);
         utils::create_dir(&ma_bin_dir)?;
 
+        let afl_cc = format!("{}/squirrel/AFLplusplus/afl-cc", FUZZERS_DIR.as_str());
+        let afl_cxx = format!("{}/squirrel/AFLplusplus/afl-c++", FUZZERS_DIR.as_str());
         let cmake_vars = [
-            format!(
-                "-DCMAKE_C_COMPILER={}/squirrel/AFLplusplus/afl-cc",
-                FUZZERS_DIR.as_str()
-            ),
-            format!(
-                "-DCMAKE_CXX_COMPILER={}/squirrel/AFLplusplus/afl-c++",
-                FUZZERS_DIR.as_str()
-            ),
-            format!("-DCMAKE_INSTALL_PREFIX={}/mariadb", INSTALL_DIR.as_str()),
+            format!("-DCMAKE_C_COMPILER={}", afl_cc),
+            format!("-DCMAKE_CXX_COMPILER={}", afl_cxx),
+            format!("-DCMAKE_INSTALL_PREFIX={}", ma_install_dir),
             format!("-DCMAKE_CXX_FLAGS='{}'", CFLAGS),
             "-DWITH_UNIT_TESTS=OFF".to_string(),
         ];
-        utils::cmake_config(&ma_src_dir, Some(&ma_bin_dir), Some(&cmake_vars))?;
-        utils::cmake_build::<&str>(&ma_src_dir, None, None, None, None)?;
-        utils::cmake_build::<&str>(&ma_src_dir, None, None, Some("install"), None)?;
+        let config_stamp = Stamp::new(
+            &stamp_target,
+            "cmake-config",
+            &[
+                source_stamp.hash().to_string(),
+                cmake_vars.join(" "),
+                stamp::compiler_id(&afl_cc),
+                stamp::compiler_id(&afl_cxx),
+                Stamp::current("squirrel", "afl-build"),
+            ],
+        );
+        config_stamp.run(force, || {
+            utils::cmake_config(
+                &ma_src_dir,
+                Some(&ma_bin_dir),
+                Some(&cmake_vars),
+                &utils::log_file("build", &stamp_target, "cmake-config"),
+            )
+        })?;
+
+        let build_stamp = Stamp::new(&stamp_target, "cmake-build", &[config_stamp.hash()]);
+        build_stamp.run(force, || {
+            utils::cmake_build::<&str>(
+                &ma_src_dir,
+                None,
+                None,
+                None,
+                None,
+                &utils::log_file("build", &stamp_target, "cmake-build"),
+            )
+        })?;
+
+        let install_stamp = Stamp::new(&stamp_target, "cmake-install", &[build_stamp.hash()]);
+        install_stamp.run(force, || {
+            utils::cmake_build::<&str>(
+                &ma_src_dir,
+                None,
+                None,
+                Some("install"),
+                None,
+                &utils::log_file("build", &stamp_target, "cmake-install"),
+            )
+        })?;
 
         Ok(())
     }
@@ -1,17 +1,27 @@
+pub use custom::CustomBuilder;
+pub use sqlsmith::SqlsmithBuilder;
 pub use squirrel::SquirrelBuilder;
+pub use stamp::Stamp;
 
 pub use boost::BoostBuilder;
 pub use mariadb::MariadbBuilder;
 pub use mysql::MysqlBuilder;
 pub use pgsql::PgsqlBuilder;
+pub use sqlite::SqliteBuilder;
 
+mod custom;
+mod sqlsmith;
 mod squirrel;
+mod stamp;
 
 mod boost;
 mod mariadb;
 mod mysql;
 mod pgsql;
+mod sqlite;
+
+use crate::target::TargetsConfig;
 
 pub trait Builder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>>;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>>;
 }
@@ -1,74 +1,115 @@
 use crate::{
-    constants::{
-        CFLAGS, DB_DIR, FUZZERS_DIR, INSTALL_DIR, MYSQL_TCP_PORT, MYSQL_UNIX_ADDR, MYSQL_VERSION,
-    },
-    utils::{self, ShellCommand},
+    constants::{CFLAGS, FUZZERS_DIR, MYSQL_TCP_PORT, MYSQL_UNIX_ADDR},
+    source,
+    target::{self, TargetsConfig},
+    utils,
 };
 
-use super::Builder;
+use super::{stamp, Builder, Stamp};
 
 pub struct MysqlBuilder;
 
 impl Builder for MysqlBuilder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>> {
-        Self::build_with_squirrel()?;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let force = targets.forced("mysql");
+        for version in targets.versions("mysql") {
+            Self::build_with_squirrel(&version, &targets.selected("boost"), force)?;
+        }
 
         Ok(())
     }
 }
 
 impl MysqlBuilder {
-    fn download() -> Result<(), Box<dyn std::error::Error>> {
-        let my_src_dir = format!("{}/mysql", DB_DIR.as_str());
-        utils::git_clone(
-            "https://github.com/mysql/mysql-server.git",
-            Some(&my_src_dir),
-        )?;
+    fn download(version: &str, force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
+        let my_src_dir = target::src_dir("mysql", version);
 
-        let cmd = ["git", "checkout", MYSQL_VERSION];
-        ShellCommand::new()
-            .args(&cmd)
-            .current_dir(&my_src_dir)
-            .spawn()?
-            .wait_with_output()?;
+        let source = source::get("mysql", version)?;
+        let source_stamp = Stamp::new(
+            &format!("mysql-{}", version),
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&my_src_dir).exists() {
+                utils::remove_dir(&my_src_dir)?;
+            }
+            source.checkout(&my_src_dir)
+        })?;
 
-        Ok(())
+        Ok(source_stamp)
     }
 
-    fn build_with_squirrel() -> Result<(), Box<dyn std::error::Error>> {
-        let my_install_dir = format!("{}/mysql", INSTALL_DIR.as_str());
-        if std::path::Path::new(&my_install_dir).exists() {
-            return Ok(());
-        }
-
-        let my_src_dir = format!("{}/mysql", DB_DIR.as_str());
-        if !std::path::Path::new(&my_src_dir).exists() {
-            Self::download()?;
-        }
+    fn build_with_squirrel(
+        version: &str,
+        boost_version: &str,
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let stamp_target = format!("mysql-{}", version);
+        let my_install_dir = target::install_dir("mysql", version);
+        let my_src_dir = target::src_dir("mysql", version);
+        let source_stamp = Self::download(version, force)?;
 
         let my_bin_dir = format!("{}/build", my_src_dir);
         utils::create_dir(&my_bin_dir)?;
 
+        let afl_cc = format!("{}/squirrel/AFLplusplus/afl-cc", FUZZERS_DIR.as_str());
+        let afl_cxx = format!("{}/squirrel/AFLplusplus/afl-c++", FUZZERS_DIR.as_str());
         let cmake_vars = [
-            format!(
-                "-DCMAKE_C_COMPILER={}/squirrel/AFLplusplus/afl-cc",
-                FUZZERS_DIR.as_str()
-            ),
-            format!(
-                "-DCMAKE_CXX_COMPILER={}/squirrel/AFLplusplus/afl-c++",
-                FUZZERS_DIR.as_str()
-            ),
-            format!("-DCMAKE_INSTALL_PREFIX={}/mysql", INSTALL_DIR.as_str()),
+            format!("-DCMAKE_C_COMPILER={}", afl_cc),
+            format!("-DCMAKE_CXX_COMPILER={}", afl_cxx),
+            format!("-DCMAKE_INSTALL_PREFIX={}", my_install_dir),
             format!("-DCMAKE_CXX_FLAGS='{}'", CFLAGS),
             format!("-DMYSQL_TCP_PORT={}", MYSQL_TCP_PORT),
             format!("-DMYSQL_UNIX_ADDR={}", MYSQL_UNIX_ADDR),
-            format!("-DWITH_BOOST={}/boost", DB_DIR.as_str()),
+            format!("-DWITH_BOOST={}", target::src_dir("boost", boost_version)),
             "-DDOWNLOAD_BOOST=1".to_string(),
             "-DWITH_UNIT_TESTS=OFF".to_string(),
         ];
-        utils::cmake_config(&my_src_dir, Some(&my_bin_dir), Some(&cmake_vars))?;
-        utils::cmake_build::<&str>(&my_src_dir, None, None, None, None)?;
-        utils::cmake_build::<&str>(&my_src_dir, None, None, Some("install"), None)?;
+        let config_stamp = Stamp::new(
+            &stamp_target,
+            "cmake-config",
+            &[
+                source_stamp.hash().to_string(),
+                cmake_vars.join(" "),
+                stamp::compiler_id(&afl_cc),
+                stamp::compiler_id(&afl_cxx),
+                Stamp::current("squirrel", "afl-build"),
+                Stamp::current(&format!("boost-{}", boost_version), "source"),
+            ],
+        );
+        config_stamp.run(force, || {
+            utils::cmake_config(
+                &my_src_dir,
+                Some(&my_bin_dir),
+                Some(&cmake_vars),
+                &utils::log_file("build", &stamp_target, "cmake-config"),
+            )
+        })?;
+
+        let build_stamp = Stamp::new(&stamp_target, "cmake-build", &[config_stamp.hash()]);
+        build_stamp.run(force, || {
+            utils::cmake_build::<&str>(
+                &my_src_dir,
+                None,
+                None,
+                None,
+                None,
+                &utils::log_file("build", &stamp_target, "cmake-build"),
+            )
+        })?;
+
+        let install_stamp = Stamp::new(&stamp_target, "cmake-install", &[build_stamp.hash()]);
+        install_stamp.run(force, || {
+            utils::cmake_build::<&str>(
+                &my_src_dir,
+                None,
+                None,
+                Some("install"),
+                None,
+                &utils::log_file("build", &stamp_target, "cmake-install"),
+            )
+        })?;
 
         Ok(())
     }
@@ -1,92 +1,93 @@
 use crate::{
-    constants::{CFLAGS, DB_DIR, FUZZERS_DIR, INSTALL_DIR, PGSQL_VERSION},
+    constants::{CFLAGS, FUZZERS_DIR},
+    source,
+    target::{self, TargetsConfig},
     utils::{self, ShellCommand},
 };
 
-use super::Builder;
+use super::{stamp, Builder, Stamp};
 
 pub struct PgsqlBuilder;
 
 impl Builder for PgsqlBuilder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>> {
-        Self::build_with_squirrel()?;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        for version in targets.versions("postgresql") {
+            Self::build_with_squirrel(&version, targets.forced("postgresql"))?;
+        }
 
         Ok(())
     }
 }
 
 impl PgsqlBuilder {
-    fn download() -> Result<(), Box<dyn std::error::Error>> {
-        let target = format!("postgresql-{}.tar.bz2", PGSQL_VERSION);
-        let target_path = format!("{}/{}", DB_DIR.as_str(), target);
-
-        let url = format!(
-            "https://ftp.postgresql.org/pub/source/v{}/{}",
-            PGSQL_VERSION, target
+    fn download(version: &str, force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
+        let pgsql_src_dir = target::src_dir("postgresql", version);
+
+        let source = source::get("postgresql", version)?;
+        let source_stamp = Stamp::new(
+            &format!("postgresql-{}", version),
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
         );
-
-        if !std::path::Path::new(&target_path).exists() {
-            let download_cmd = ["wget", &url];
-
-            ShellCommand::new()
-                .args(&download_cmd)
-                .current_dir(DB_DIR.as_str())
-                .spawn()?
-                .wait_with_output()?;
-        }
-
-        let extract_cmd = ["tar", "xvf", &target_path];
-        ShellCommand::new()
-            .args(&extract_cmd)
-            .current_dir(DB_DIR.as_str())
-            .spawn()?
-            .wait_with_output()?;
-
-        let raw_src_dir = format!("postgresql-{}", PGSQL_VERSION);
-        let other_cmd = ["mv", &raw_src_dir, "postgresql"];
-        ShellCommand::new()
-            .args(&other_cmd)
-            .current_dir(DB_DIR.as_str())
-            .spawn()?
-            .wait_with_output()?;
-
-        Ok(())
+        source_stamp.run(force, || {
+            if std::path::Path::new(&pgsql_src_dir).exists() {
+                utils::remove_dir(&pgsql_src_dir)?;
+            }
+            source.checkout(&pgsql_src_dir)
+        })?;
+
+        Ok(source_stamp)
     }
 
-    fn build_with_squirrel() -> Result<(), Box<dyn std::error::Error>> {
+    fn build_with_squirrel(version: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
         let squirrel_fuzzers_dir = format!("{}/squirrel", FUZZERS_DIR.as_str());
+        let stamp_target = format!("postgresql-{}", version);
 
-        let pgsql_install_dir = format!("{}/postgresql", INSTALL_DIR.as_str());
-        if std::path::Path::new(&pgsql_install_dir).exists() {
-            return Ok(());
-        }
-
-        let pgsql_src_dir = format!("{}/postgresql", DB_DIR.as_str());
-        if !std::path::Path::new(&pgsql_src_dir).exists() {
-            Self::download()?;
-        }
+        let pgsql_install_dir = target::install_dir("postgresql", version);
+        let pgsql_src_dir = target::src_dir("postgresql", version);
+        let source_stamp = Self::download(version, force)?;
 
         let pgsql_bin_dir = format!("{}/build", pgsql_src_dir);
         utils::create_dir(&pgsql_bin_dir)?;
 
+        let afl_cc = format!("{}/AFLplusplus/afl-cc", squirrel_fuzzers_dir);
         let config_cmd = [
             "../configure".to_string(),
             format!("--prefix={}", pgsql_install_dir),
-            format!("--with-CC={}/AFLplusplus/afl-cc", squirrel_fuzzers_dir),
+            format!("--with-CC={}", afl_cc),
             format!("CFLAGS='{}'", CFLAGS),
         ];
-        ShellCommand::new()
-            .args(&config_cmd)
-            .current_dir(&pgsql_bin_dir)
-            .spawn()?
-            .wait_with_output()?;
+        let config_stamp = Stamp::new(
+            &stamp_target,
+            "configure",
+            &[
+                source_stamp.hash().to_string(),
+                config_cmd.join(" "),
+                stamp::compiler_id(&afl_cc),
+                Stamp::current("squirrel", "afl-build"),
+            ],
+        );
+        config_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&config_cmd)
+                .current_dir(&pgsql_bin_dir)
+                .log_to(utils::log_file("build", &stamp_target, "configure"))
+                .run()
+        })?;
 
         let build_cmd = ["make", "world-bin", "&&", "make", "install-world-bin"];
-        ShellCommand::new()
-            .args(&build_cmd)
-            .current_dir(&pgsql_bin_dir)
-            .spawn()?
-            .wait_with_output()?;
+        let build_stamp = Stamp::new(
+            &stamp_target,
+            "make",
+            &[config_stamp.hash().to_string(), build_cmd.join(" ")],
+        );
+        build_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&build_cmd)
+                .current_dir(&pgsql_bin_dir)
+                .log_to(utils::log_file("build", &stamp_target, "make"))
+                .run()
+        })?;
 
         Ok(())
     }
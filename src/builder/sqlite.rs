@@ -0,0 +1,123 @@
+use crate::{
+    constants::{CFLAGS, FUZZERS_DIR},
+    source,
+    target::{self, TargetsConfig},
+    utils::{self, ShellCommand},
+};
+
+use super::{stamp, Builder, Stamp};
+
+pub struct SqliteBuilder;
+
+impl Builder for SqliteBuilder {
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        for version in targets.versions("sqlite") {
+            Self::build_with_squirrel(&version, targets.forced("sqlite"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SqliteBuilder {
+    fn download(version: &str, force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
+        let sqlite_src_dir = target::src_dir("sqlite", version);
+
+        let source = source::get("sqlite", version)?;
+        let source_stamp = Stamp::new(
+            &format!("sqlite-{}", version),
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&sqlite_src_dir).exists() {
+                utils::remove_dir(&sqlite_src_dir)?;
+            }
+            source.checkout(&sqlite_src_dir)
+        })?;
+
+        Ok(source_stamp)
+    }
+
+    /// Build the amalgamation and link it into the `ossfuzz` harness Squirrel fuzzes.
+    fn build_with_squirrel(version: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let stamp_target = format!("sqlite-{}", version);
+        let sqlite_install_dir = target::install_dir("sqlite", version);
+        let sqlite_src_dir = target::src_dir("sqlite", version);
+        let source_stamp = Self::download(version, force)?;
+
+        let sqlite_bin_dir = format!("{}/build", sqlite_src_dir);
+        utils::create_dir(&sqlite_bin_dir)?;
+
+        let afl_cc = format!("{}/squirrel/AFLplusplus/afl-cc", FUZZERS_DIR.as_str());
+        let config_cmd = [
+            "../configure".to_string(),
+            format!("CC={}", afl_cc),
+            format!("CFLAGS='{}'", CFLAGS),
+        ];
+        let config_stamp = Stamp::new(
+            &stamp_target,
+            "configure",
+            &[
+                source_stamp.hash().to_string(),
+                config_cmd.join(" "),
+                stamp::compiler_id(&afl_cc),
+                Stamp::current("squirrel", "afl-build"),
+            ],
+        );
+        config_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&config_cmd)
+                .current_dir(&sqlite_bin_dir)
+                .log_to(utils::log_file("build", &stamp_target, "configure"))
+                .run()
+        })?;
+
+        // Generating `sqlite3.c` from the canonical sources needs `tclsh`.
+        let amalgamation_cmd = ["make", "sqlite3.c"];
+        let amalgamation_stamp = Stamp::new(
+            &stamp_target,
+            "amalgamation",
+            &[config_stamp.hash().to_string(), amalgamation_cmd.join(" ")],
+        );
+        amalgamation_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&amalgamation_cmd)
+                .current_dir(&sqlite_bin_dir)
+                .log_to(utils::log_file("build", &stamp_target, "amalgamation"))
+                .run()
+        })?;
+
+        let harness_cmd = [
+            afl_cc.clone(),
+            CFLAGS.to_string(),
+            "-fsanitize=fuzzer".to_string(),
+            "-I.".to_string(),
+            "-DSQLITE_THREADSAFE=0".to_string(),
+            "-DSQLITE_ENABLE_LOAD_EXTENSION=0".to_string(),
+            "-DSQLITE_DEBUG".to_string(),
+            "../test/ossfuzz.c".to_string(),
+            "sqlite3.c".to_string(),
+            "-o".to_string(),
+            format!("{}/bin/ossfuzz", sqlite_install_dir),
+            "-ldl".to_string(),
+            "-lpthread".to_string(),
+            "-lm".to_string(),
+        ];
+        let harness_stamp = Stamp::new(
+            &stamp_target,
+            "harness",
+            &[amalgamation_stamp.hash().to_string(), harness_cmd.join(" ")],
+        );
+        harness_stamp.run(force, || {
+            utils::create_dir(&format!("{}/bin", sqlite_install_dir))?;
+            ShellCommand::new()
+                .args(&harness_cmd)
+                .current_dir(&sqlite_bin_dir)
+                .log_to(utils::log_file("build", &stamp_target, "harness"))
+                .run()
+        })?;
+
+        Ok(())
+    }
+}
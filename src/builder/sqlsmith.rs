@@ -0,0 +1,80 @@
+use crate::{
+    constants::{FUZZERS_DIR, SQLSMITH_VERSION},
+    source,
+    target::TargetsConfig,
+    utils::{self, ShellCommand},
+};
+
+use super::{stamp, Builder, Stamp};
+
+pub struct SqlsmithBuilder;
+
+impl Builder for SqlsmithBuilder {
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let force = targets.forced("sqlsmith");
+        let source_stamp = Self::download(force)?;
+        Self::build(&source_stamp, force)?;
+
+        Ok(())
+    }
+}
+
+impl SqlsmithBuilder {
+    fn download(force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
+        let sqlsmith_src_dir = format!("{}/sqlsmith", FUZZERS_DIR.as_str());
+
+        let source = source::get("sqlsmith", SQLSMITH_VERSION)?;
+        let source_stamp = Stamp::new(
+            "sqlsmith",
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&sqlsmith_src_dir).exists() {
+                utils::remove_dir(&sqlsmith_src_dir)?;
+            }
+            source.checkout(&sqlsmith_src_dir)
+        })?;
+
+        Ok(source_stamp)
+    }
+
+    /// SQLsmith links against the system libpqxx and Boost.Regex.
+    fn build(source_stamp: &Stamp, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let sqlsmith_src_dir = format!("{}/sqlsmith", FUZZERS_DIR.as_str());
+
+        let config_cmd = ["autoreconf", "-i", "&&", "./configure"];
+        let config_stamp = Stamp::new(
+            "sqlsmith",
+            "configure",
+            &[
+                source_stamp.hash().to_string(),
+                config_cmd.join(" "),
+                stamp::compiler_id("c++"),
+            ],
+        );
+        config_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&config_cmd)
+                .current_dir(&sqlsmith_src_dir)
+                .log_to(utils::log_file("build", "sqlsmith", "configure"))
+                .run()
+        })?;
+
+        let build_cmd = ["make", "-j"];
+        let build_stamp = Stamp::new(
+            "sqlsmith",
+            "make",
+            &[config_stamp.hash().to_string(), build_cmd.join(" ")],
+        );
+        build_stamp.run(force, || {
+            ShellCommand::new()
+                .args(&build_cmd)
+                .current_dir(&sqlsmith_src_dir)
+                .log_to(utils::log_file("build", "sqlsmith", "make"))
+                .run()
+        })?;
+
+        Ok(())
+    }
+}
@@ -1,33 +1,45 @@
-use crate::{constants::FUZZERS_DIR, utils};
+use crate::{
+    constants::{FUZZERS_DIR, SQUIRREL_VERSION},
+    source,
+    target::TargetsConfig,
+    utils,
+};
 
-use super::Builder;
+use super::{stamp, Builder, Stamp};
 
 pub struct SquirrelBuilder;
 
 impl Builder for SquirrelBuilder {
-    fn setup() -> Result<(), Box<dyn std::error::Error>> {
-        Self::download()?;
-        Self::build()?;
+    fn setup(targets: &TargetsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let force = targets.forced("squirrel");
+        let source_stamp = Self::download(force)?;
+        Self::build(&source_stamp, force)?;
 
         Ok(())
     }
 }
 
 impl SquirrelBuilder {
-    fn download() -> Result<(), Box<dyn std::error::Error>> {
+    fn download(force: bool) -> Result<Stamp, Box<dyn std::error::Error>> {
         let squirrel_src_dir = format!("{}/squirrel", FUZZERS_DIR.as_str());
 
-        if !std::path::Path::new(&squirrel_src_dir).exists() {
-            utils::git_clone(
-                "https://github.com/s3team/Squirrel.git",
-                Some(&squirrel_src_dir),
-            )?;
-        }
+        let source = source::get("squirrel", SQUIRREL_VERSION)?;
+        let source_stamp = Stamp::new(
+            "squirrel",
+            "source",
+            &[source.key(), source.pinned().unwrap_or_default()],
+        );
+        source_stamp.run(force, || {
+            if std::path::Path::new(&squirrel_src_dir).exists() {
+                utils::remove_dir(&squirrel_src_dir)?;
+            }
+            source.checkout(&squirrel_src_dir)
+        })?;
 
-        Ok(())
+        Ok(source_stamp)
     }
 
-    fn build() -> Result<(), Box<dyn std::error::Error>> {
+    fn build(source_stamp: &Stamp, force: bool) -> Result<(), Box<dyn std::error::Error>> {
         let squirrel_src_dir = format!("{}/squirrel", FUZZERS_DIR.as_str());
         let squirrel_bin_dir = format!("{}/build", &squirrel_src_dir);
         utils::create_dir(&squirrel_bin_dir)?;
@@ -40,16 +52,55 @@ impl SquirrelBuilder {
             "-DPOSTGRESQL=ON",
         ];
 
-        utils::cmake_config(&squirrel_src_dir, None, Some(&cmake_vars))?;
-        utils::cmake_build::<&str>(&squirrel_src_dir, None, None, None, None)?;
+        let config_stamp = Stamp::new(
+            "squirrel",
+            "cmake-config",
+            &[
+                source_stamp.hash().to_string(),
+                cmake_vars.join(" "),
+                stamp::compiler_id("/usr/bin/clang"),
+                stamp::compiler_id("/usr/bin/clang++"),
+            ],
+        );
+        config_stamp.run(force, || {
+            utils::cmake_config(
+                &squirrel_src_dir,
+                None,
+                Some(&cmake_vars),
+                &utils::log_file("build", "squirrel", "cmake-config"),
+            )
+        })?;
+
+        let build_stamp = Stamp::new("squirrel", "cmake-build", &[config_stamp.hash()]);
+        build_stamp.run(force, || {
+            utils::cmake_build::<&str>(
+                &squirrel_src_dir,
+                None,
+                None,
+                None,
+                None,
+                &utils::log_file("build", "squirrel", "cmake-build"),
+            )
+        })?;
 
         let afl_src_dir = format!("{}/AFLplusplus", &squirrel_src_dir);
         let build_afl_cmd = ["make", "-j"];
-        utils::ShellCommand::new()
-            .args(&build_afl_cmd)
-            .current_dir(&afl_src_dir)
-            .spawn()?
-            .wait_with_output()?;
+        let afl_stamp = Stamp::new(
+            "squirrel",
+            "afl-build",
+            &[
+                source_stamp.hash().to_string(),
+                build_afl_cmd.join(" "),
+                stamp::compiler_id("cc"),
+            ],
+        );
+        afl_stamp.run(force, || {
+            utils::ShellCommand::new()
+                .args(&build_afl_cmd)
+                .current_dir(&afl_src_dir)
+                .log_to(utils::log_file("build", "squirrel", "afl-build"))
+                .run()
+        })?;
 
         Ok(())
     }
@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::{constants::STAMP_DIR, utils};
+
+/// Record of a finished build step, keyed by the hash of everything it depends on
+pub struct Stamp {
+    path: String,
+    hash: String,
+    inputs: Vec<String>,
+}
+
+impl Stamp {
+    /// Stamp of `step` of `target`, e.g. `mysql-8.0`, over the given inputs.
+    pub fn new<S: AsRef<str>>(target: &str, step: &str, inputs: &[S]) -> Self {
+        let inputs = inputs
+            .iter()
+            .map(|e| e.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        let mut hasher = Sha256::new();
+        for input in &inputs {
+            hasher.update(input.as_bytes());
+            hasher.update([0]);
+        }
+        let hash = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        Self {
+            path: Self::path(target, step),
+            hash,
+            inputs,
+        }
+    }
+
+    /// Hash recorded by the last successful run of a step, empty if it never ran
+    pub fn current(target: &str, step: &str) -> String {
+        fs::read_to_string(Self::path(target, step))
+            .ok()
+            .and_then(|content| content.lines().next().map(|l| l.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// Inputs of every step of `target` that ran successfully, keyed by step
+    pub fn recorded(target: &str) -> BTreeMap<String, Vec<String>> {
+        let dir = format!("{}/{}", STAMP_DIR.as_str(), target);
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let step = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_suffix(".stamp")?
+                    .to_string();
+                let content = fs::read_to_string(&path).ok()?;
+                // The first line is the hash, the inputs follow one per line.
+                Some((
+                    step,
+                    content.lines().skip(1).map(|l| l.to_string()).collect(),
+                ))
+            })
+            .collect()
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        fs::read_to_string(&self.path)
+            .map(|content| content.lines().next() == Some(self.hash.as_str()))
+            .unwrap_or(false)
+    }
+
+    /// Run `step` unless its stamp is fresh, recording the stamp once it succeeds.
+    pub fn run<F>(&self, force: bool, step: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Result<(), Box<dyn std::error::Error>>,
+    {
+        if !force && self.is_fresh() {
+            return Ok(());
+        }
+
+        // A failed step must not leave the previous stamp behind.
+        if Path::new(&self.path).exists() {
+            fs::remove_file(&self.path)?;
+        }
+        step()?;
+
+        if let Some(parent) = Path::new(&self.path).parent() {
+            utils::create_dir(&parent.display().to_string())?;
+        }
+        let mut content = format!("{}\n", self.hash);
+        for input in &self.inputs {
+            content.push_str(&format!("{}\n", input));
+        }
+        fs::write(&self.path, content)?;
+
+        Ok(())
+    }
+
+    fn path(target: &str, step: &str) -> String {
+        format!("{}/{}/{}.stamp", STAMP_DIR.as_str(), target, step)
+    }
+}
+
+/// Compiler path together with its reported version
+pub fn compiler_id(path: &str) -> String {
+    let version = std::process::Command::new(path)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .map(|l| l.to_string())
+        })
+        .unwrap_or_default();
+
+    format!("{} {}", path, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_hash() {
+        let a = Stamp::new("postgresql-15.0", "configure", &["15.0", "-O2"]);
+        let b = Stamp::new("postgresql-15.0", "configure", &["15.0", "-O2"]);
+        let c = Stamp::new("postgresql-15.0", "configure", &["15.0", "-O3"]);
+        let d = Stamp::new("postgresql-15.0", "configure", &["15.0-", "O2"]);
+
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), c.hash());
+        assert_ne!(a.hash(), d.hash());
+    }
+}
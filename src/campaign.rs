@@ -0,0 +1,514 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{LOG_DIR, RUNS_DIR, SOCKET_DIR},
+    runner::cgroup,
+    target, utils, CAMPAIGN_TARGETS,
+};
+
+/// Timestamp a run name starts with, e.g. `20240131-093000`
+const RUN_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const RUN_TIMESTAMP_LEN: usize = "20240131-093000".len();
+
+/// Symlink to the newest run in `RUNS_DIR`
+const LATEST: &str = "latest";
+
+/// One trial of a fuzzer against one version of a target, within a run of an experiment
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Campaign {
+    /// Experiment the campaign belongs to, e.g. `baseline`
+    pub experiment: String,
+    /// Run of the experiment, naming its directory under `runs/`
+    pub run: String,
+    /// Built-in or configured fuzzer, e.g. `squirrel`
+    pub fuzzer: String,
+    /// Target, e.g. `postgresql`
+    pub target: String,
+    pub version: String,
+    /// Trial number, starting from 1
+    pub trial: u32,
+    /// Time budget in seconds, unbounded if absent
+    pub duration_secs: Option<u64>,
+}
+
+impl Campaign {
+    /// Short name of the target used in campaign names, e.g. `pg`
+    pub fn suffix(&self) -> &'static str {
+        CAMPAIGN_TARGETS
+            .iter()
+            .find(|(_, t, _)| *t == self.target)
+            .map(|(suffix, _, _)| *suffix)
+            .unwrap_or("unknown")
+    }
+
+    /// Fuzzer and target, e.g. `squirrel-pg`
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.fuzzer, self.suffix())
+    }
+
+    /// Unique within its run, e.g. `squirrel-postgresql-trial1`
+    pub fn id(&self) -> String {
+        format!("{}-{}-trial{}", self.fuzzer, self.target, self.trial)
+    }
+
+    /// Unique across runs, names the port, cores and cgroup of the campaign
+    pub fn key(&self) -> String {
+        format!("{}-{}", self.run, self.id())
+    }
+
+    /// Log directory of the campaign under `logs/run`
+    pub fn log_target(&self) -> String {
+        format!("{}/{}", self.run, self.id())
+    }
+
+    pub fn dir(&self) -> String {
+        format!("{}/{}", run_dir(&self.run), self.id())
+    }
+
+    /// Samples of the campaign monitor
+    pub fn output_csv(&self) -> String {
+        format!("{}/monitor.csv", self.dir())
+    }
+
+    /// Coverage-instrumented binary of the target
+    pub fn target_bin(&self) -> String {
+        let exec = CAMPAIGN_TARGETS
+            .iter()
+            .find(|(_, t, _)| *t == self.target)
+            .map(|(_, _, exec)| *exec)
+            .unwrap_or_default();
+
+        format!(
+            "{}/{}",
+            target::install_dir(&self.target, &self.version),
+            exec
+        )
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_secs.map(Duration::from_secs)
+    }
+
+    /// Launch time of the campaign, once it recorded itself
+    pub fn started(&self) -> Option<SystemTime> {
+        fs::metadata(format!("{}/campaign.json", self.dir()))
+            .ok()?
+            .modified()
+            .ok()
+    }
+
+    /// Record the campaign in its directory, for the commands inspecting it later.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(self.dir())?;
+        fs::write(
+            format!("{}/campaign.json", self.dir()),
+            serde_json::to_string_pretty(self)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Directory holding the campaigns and results of a run
+pub fn run_dir(run: &str) -> String {
+    format!("{}/{}", RUNS_DIR.as_str(), run)
+}
+
+/// Names of all runs found on disk, oldest first
+pub fn runs() -> Vec<String> {
+    let mut runs = fs::read_dir(RUNS_DIR.as_str())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|run| started(run).is_some())
+        .collect::<Vec<_>>();
+    runs.sort();
+
+    runs
+}
+
+/// Name of a new run of `experiment` starting now
+pub fn new_run(experiment: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if experiment.is_empty() || experiment == LATEST || experiment.contains('/') {
+        return Err(format!("Invalid experiment name {}", experiment).into());
+    }
+
+    let run = format!(
+        "{}-{}",
+        Local::now().format(RUN_TIMESTAMP_FORMAT),
+        experiment
+    );
+    if Path::new(&run_dir(&run)).exists() {
+        return Err(format!("Run {} already exists", run).into());
+    }
+
+    Ok(run)
+}
+
+/// Repoint the `latest` symlink, removing it if there is no run left.
+fn point_latest(run: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let latest = run_dir(LATEST);
+    if fs::symlink_metadata(&latest).is_ok() {
+        fs::remove_file(&latest)?;
+    }
+    if let Some(run) = run {
+        std::os::unix::fs::symlink(run, &latest)?;
+    }
+
+    Ok(())
+}
+
+/// Run named by `name`: a run itself, `latest`, or an experiment standing for its newest run
+pub fn resolve(name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if name == LATEST {
+        let run = fs::read_link(run_dir(LATEST))
+            .map_err(|_| format!("No run in {}", RUNS_DIR.as_str()))?;
+        return Ok(run.display().to_string());
+    }
+    if started(name).is_some() && Path::new(&run_dir(name)).is_dir() {
+        return Ok(name.to_string());
+    }
+
+    runs()
+        .into_iter()
+        .rev()
+        .find(|run| experiment_of(run) == Some(name))
+        .ok_or_else(|| format!("No run of {} in {}", name, RUNS_DIR.as_str()).into())
+}
+
+/// Start time of `run`, parsed from its name
+pub fn started(run: &str) -> Option<NaiveDateTime> {
+    let timestamp = run.get(..RUN_TIMESTAMP_LEN)?;
+    NaiveDateTime::parse_from_str(timestamp, RUN_TIMESTAMP_FORMAT).ok()
+}
+
+/// Experiment `run` belongs to
+pub fn experiment_of(run: &str) -> Option<&str> {
+    started(run)?;
+    run.get(RUN_TIMESTAMP_LEN + 1..)
+}
+
+/// Which runs `clean` removes besides a named one
+#[derive(Default)]
+pub struct Retention {
+    /// Keep only the newest runs, per experiment
+    pub keep_last: Option<usize>,
+    /// Remove runs started longer ago
+    pub older_than: Option<Duration>,
+    /// Only consider the runs of this experiment
+    pub experiment: Option<String>,
+}
+
+impl Retention {
+    /// Runs of `runs`, oldest first, that fall outside the policy at `now`
+    pub fn expired(&self, runs: &[String], now: NaiveDateTime) -> Vec<String> {
+        let mut expired = vec![];
+        for (i, run) in runs.iter().enumerate() {
+            let Some(experiment) = experiment_of(run) else {
+                continue;
+            };
+            if self.experiment.as_deref().is_some_and(|e| e != experiment) {
+                continue;
+            }
+
+            let newer = runs[i + 1..]
+                .iter()
+                .filter(|r| experiment_of(r) == Some(experiment))
+                .count();
+            let too_many = self.keep_last.is_some_and(|keep| newer >= keep);
+            let too_old = match (self.older_than, started(run)) {
+                (Some(max_age), Some(start)) => {
+                    (now - start).to_std().unwrap_or_default() > max_age
+                }
+                _ => false,
+            };
+            if too_many || too_old {
+                expired.push(run.clone());
+            }
+        }
+
+        expired
+    }
+}
+
+/// Campaigns recorded for `run`
+pub fn load(run: &str) -> Result<Vec<Campaign>, Box<dyn std::error::Error>> {
+    let dir = run_dir(run);
+    if !Path::new(&dir).exists() {
+        return Err(format!("No run {} in {}", run, RUNS_DIR.as_str()).into());
+    }
+
+    let mut campaigns = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let path = entry.path().join("campaign.json");
+        if !path.exists() {
+            continue;
+        }
+        // Interrupted campaigns set aside keep their record, under another directory name.
+        let campaign: Campaign = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if entry.file_name().to_str() == Some(campaign.id().as_str()) {
+            campaigns.push(campaign);
+        }
+    }
+    campaigns.sort_by_key(|c: &Campaign| c.id());
+
+    Ok(campaigns)
+}
+
+/// Pid file of the `run` process driving a run
+fn run_pid_file(run: &str) -> String {
+    format!("{}/run.pid", run_dir(run))
+}
+
+/// Mark `run` as driven by this process, and as the latest run.
+pub fn record_run(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(pid) = running_pid(run) {
+        return Err(format!("Run {} is already running as pid {}", run, pid).into());
+    }
+    utils::create_dir(&run_dir(run))?;
+    fs::write(run_pid_file(run), std::process::id().to_string())?;
+    point_latest(Some(run))?;
+
+    Ok(())
+}
+
+pub fn finish_run(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let pid_file = run_pid_file(run);
+    if Path::new(&pid_file).exists() {
+        fs::remove_file(pid_file)?;
+    }
+
+    Ok(())
+}
+
+/// Pid of the `run` process of `run`, if it is still alive
+pub fn running_pid(run: &str) -> Option<u32> {
+    let pid = fs::read_to_string(run_pid_file(run))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+
+    pid_alive(pid).then_some(pid)
+}
+
+pub(crate) fn pid_alive(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+/// All processes below `pid`, in breadth-first order
+fn descendants(pid: u32) -> Vec<u32> {
+    let parents = fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|p| {
+            // The parent pid is the second field after the parenthesized command name.
+            let stat = fs::read_to_string(format!("/proc/{}/stat", p)).ok()?;
+            let ppid = stat
+                .rsplit_once(')')?
+                .1
+                .split_whitespace()
+                .nth(1)?
+                .parse()
+                .ok()?;
+            Some((p, ppid))
+        })
+        .collect::<Vec<(u32, u32)>>();
+
+    let mut found = vec![pid];
+    let mut i = 0;
+    while i < found.len() {
+        let parent = found[i];
+        found.extend(
+            parents
+                .iter()
+                .filter(|(_, pp)| *pp == parent)
+                .map(|(p, _)| *p),
+        );
+        i += 1;
+    }
+    found.remove(0);
+
+    found
+}
+
+fn signal(pid: u32, sig: &str) {
+    let _ = Command::new("kill")
+        .args([format!("-{}", sig), pid.to_string()])
+        .status();
+}
+
+/// Stop every process of `run`: its `run` process, fuzzers and database servers.
+pub fn stop(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let campaigns = load(run)?;
+
+    if let Some(pid) = running_pid(run) {
+        // The scheduler goes first so it starts no queued campaign in their place,
+        // the fuzzers it leaves behind then exit cleanly on SIGINT.
+        let children = descendants(pid);
+        signal(pid, "TERM");
+        for child in children.into_iter().rev() {
+            signal(child, "INT");
+        }
+    }
+
+    for campaign in &campaigns {
+        kill(campaign, None)?;
+    }
+    finish_run(run)?;
+
+    Ok(())
+}
+
+/// Kill the fuzzer of `campaign` and its database server, leaving the rest of its run alone.
+pub fn kill(
+    campaign: &Campaign,
+    fuzzer_pid: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(pid) = fuzzer_pid.filter(|p| pid_alive(*p)) {
+        signal(pid, "TERM");
+    }
+
+    let kill_file = format!("{}/cgroup.kill", cgroup::campaign_cgroup(&campaign.key()));
+    if Path::new(&kill_file).exists() {
+        fs::write(kill_file, "1")?;
+    }
+
+    // Servers detach from the fuzzer, but leave their pid in the data directory.
+    let data_dir = format!("{}/data", campaign.dir());
+    for entry in fs::read_dir(&data_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e == "pid") {
+            let pid = fs::read_to_string(&path)
+                .ok()
+                .and_then(|c| c.lines().next()?.trim().parse().ok());
+            if let Some(pid) = pid.filter(|p| pid_alive(*p)) {
+                signal(pid, "TERM");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove everything `run` left behind: campaigns, results, logs, cgroups and sockets.
+pub fn clean(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(pid) = running_pid(run) {
+        return Err(format!("Run {} is still running as pid {}, stop it first", run, pid).into());
+    }
+
+    for campaign in load(run).unwrap_or_default() {
+        let _ = fs::remove_dir(cgroup::campaign_cgroup(&campaign.key()));
+        let socket_dir = format!("{}/{}", SOCKET_DIR.as_str(), campaign.key());
+        if Path::new(&socket_dir).exists() {
+            utils::remove_dir(&socket_dir)?;
+        }
+    }
+    for dir in [run_dir(run), format!("{}/run/{}", LOG_DIR.as_str(), run)] {
+        if Path::new(&dir).exists() {
+            utils::remove_dir(&dir)?;
+        }
+    }
+
+    // Keep `latest` pointing at a run that still exists.
+    if resolve(LATEST).map_or(true, |latest| latest == run) {
+        point_latest(runs().last().map(|r| r.as_str()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_campaign_names() -> Result<(), Box<dyn std::error::Error>> {
+        let campaign = Campaign {
+            experiment: "baseline".to_string(),
+            run: "20240131-093000-baseline".to_string(),
+            fuzzer: "squirrel".to_string(),
+            target: "postgresql".to_string(),
+            version: "15.0".to_string(),
+            trial: 2,
+            duration_secs: Some(3600),
+        };
+
+        assert_eq!(campaign.name(), "squirrel-pg");
+        assert_eq!(campaign.id(), "squirrel-postgresql-trial2");
+        assert_eq!(
+            campaign.key(),
+            "20240131-093000-baseline-squirrel-postgresql-trial2"
+        );
+        assert!(campaign
+            .dir()
+            .ends_with("runs/20240131-093000-baseline/squirrel-postgresql-trial2"));
+        assert!(campaign
+            .target_bin()
+            .ends_with("postgresql-15.0/bin/postgres"));
+
+        let json = serde_json::to_string(&campaign)?;
+        assert_eq!(serde_json::from_str::<Campaign>(&json)?, campaign);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention() {
+        let runs = [
+            "20240101-000000-baseline",
+            "20240102-000000-sqlsmith",
+            "20240103-000000-baseline",
+            "20240104-000000-baseline",
+        ]
+        .map(|r| r.to_string());
+        let now = started("20240105-000000-x").unwrap();
+        assert_eq!(experiment_of(&runs[1]), Some("sqlsmith"));
+        assert_eq!(experiment_of("baseline"), None);
+
+        let keep_last = Retention {
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(keep_last.expired(&runs, now), vec![runs[0].clone()]);
+
+        let older_than = Retention {
+            older_than: Some(Duration::from_secs(2 * 24 * 3600)),
+            experiment: Some("baseline".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(older_than.expired(&runs, now), vec![runs[0].clone()]);
+
+        let older_than = Retention {
+            older_than: Some(Duration::from_secs(2 * 24 * 3600)),
+            ..Default::default()
+        };
+        assert_eq!(older_than.expired(&runs, now), runs[..2].to_vec());
+        assert!(Retention::default().expired(&runs, now).is_empty());
+    }
+
+    #[test]
+    fn test_descendants() -> Result<(), Box<dyn std::error::Error>> {
+        let mut child = Command::new("sleep").arg("10").spawn()?;
+        let found = descendants(std::process::id());
+        child.kill()?;
+        child.wait()?;
+
+        assert!(found.contains(&child.id()));
+        assert!(!found.contains(&std::process::id()));
+
+        Ok(())
+    }
+}
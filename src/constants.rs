@@ -7,10 +7,24 @@ pub const NPROCS: i8 = 6;
 
 pub const CFLAGS: &str = "-fprofile-instr-generate -fcoverage-mapping";
 
+/// Squirrel git reference, it is pinned by the source manifest
+pub const SQUIRREL_VERSION: &str = "HEAD";
+
+/// SQLsmith git reference, it is pinned by the source manifest
+pub const SQLSMITH_VERSION: &str = "HEAD";
+
+/// First port tried for campaigns against each server, the allocator moves up from there
+pub const PGSQL_BASE_PORT: u16 = 15432;
+pub const MYSQL_BASE_PORT: u16 = 13306;
+pub const MARIADB_BASE_PORT: u16 = 23306;
+
+/// Time budget of fuzzers that do not run until killed, e.g. SQLsmith
+pub const CAMPAIGN_SECS: u64 = 24 * 60 * 60;
+
+/// Default target versions, overridden by `config/targets.toml`
 pub const PGSQL_VERSION: &str = "15.0";
 
 pub const BOOST_VERSION: &str = "1.77.0";
-pub const BOOST_VERSION_1: &str = "1_77_0";
 
 pub const MYSQL_VERSION: &str = "8.0";
 pub const MYSQL_TCP_PORT: &str = "3307";
@@ -18,6 +32,8 @@ pub const MYSQL_UNIX_ADDR: &str = "/tmp/another_mysql.sock";
 
 pub const MARIADB_VERSION: &str = "10.5";
 
+pub const SQLITE_VERSION: &str = "3.42.0";
+
 /// Current workspace
 pub static ROOT: Lazy<String> = Lazy::new(|| {
     std::env::current_dir()
@@ -26,6 +42,14 @@ pub static ROOT: Lazy<String> = Lazy::new(|| {
         .to_string()
 });
 
+/// Unix sockets of the campaign servers, kept short for the 107 bytes `sun_path` limit
+pub static SOCKET_DIR: Lazy<String> = Lazy::new(|| {
+    std::env::temp_dir()
+        .join("fuzzer_compare")
+        .display()
+        .to_string()
+});
+
 /// All temporary files directory
 pub static TMP_DIR: Lazy<String> = Lazy::new(|| format!("{}/tmp", ROOT.as_str()));
 
@@ -41,13 +65,58 @@ pub static INSTALL_DIR: Lazy<String> = Lazy::new(|| format!("{}/install", TMP_DI
 /// Configurations needed by fuzzer directory
 pub static CONFIG_DIR: Lazy<String> = Lazy::new(|| format!("{}/config", TMP_DIR.as_str()));
 
-/// Test files directory
-pub static TEST_DIR: Lazy<String> = Lazy::new(|| format!("{}/test", TMP_DIR.as_str()));
+/// Per-step command logs directory
+pub static LOG_DIR: Lazy<String> = Lazy::new(|| format!("{}/logs", TMP_DIR.as_str()));
+
+/// Local cache of source tarballs and bare git mirrors, `$FUZZER_COMPARE_CACHE` if set
+pub static CACHE_DIR: Lazy<String> = Lazy::new(|| {
+    std::env::var("FUZZER_COMPARE_CACHE").unwrap_or_else(|_| format!("{}/cache", ROOT.as_str()))
+});
+
+/// Pinned checksums of all sources
+pub static SOURCE_MANIFEST: Lazy<String> =
+    Lazy::new(|| format!("{}/config/sources.manifest", ROOT.as_str()));
+
+/// Campaigns and results of every run, one `<timestamp>-<experiment>` directory each
+pub static RUNS_DIR: Lazy<String> = Lazy::new(|| format!("{}/runs", ROOT.as_str()));
+
+/// SQLite database collecting the samples of every run that writes to it
+pub static HISTORY_DB: Lazy<String> = Lazy::new(|| format!("{}/history.sqlite", RUNS_DIR.as_str()));
+
+/// Build step stamps directory
+pub static STAMP_DIR: Lazy<String> = Lazy::new(|| format!("{}/stamps", TMP_DIR.as_str()));
 
-/// Monitor status output directory
-pub static OUTPUT_DIR: Lazy<String> = Lazy::new(|| format!("{}/output", TMP_DIR.as_str()));
+/// Versions of the targets to build and fuzz
+pub static TARGETS_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/targets.toml", ROOT.as_str()));
 
-/// Squirrel running envs for per database
+/// Fuzzers defined by their commands rather than a built-in runner
+pub static FUZZERS_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/fuzzers.toml", ROOT.as_str()));
+
+/// Resource limits of the campaign cgroups
+pub static CGROUP_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/cgroup.toml", ROOT.as_str()));
+
+/// Actions run when a campaign crashes, hangs, stalls or loses its server
+pub static HOOKS_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/hooks.toml", ROOT.as_str()));
+
+/// Collectors sampled for every campaign, built-in ones and custom commands
+pub static COLLECTORS_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/collectors.toml", ROOT.as_str()));
+
+/// Health rules of the watchdog and whether it restarts stalled fuzzers
+pub static WATCHDOG_CONFIG: Lazy<String> =
+    Lazy::new(|| format!("{}/config/watchdog.toml", ROOT.as_str()));
+
+/// Parent cgroup of all campaigns, `$FUZZER_COMPARE_CGROUP` if set
+pub static CGROUP_ROOT: Lazy<String> = Lazy::new(|| {
+    std::env::var("FUZZER_COMPARE_CGROUP")
+        .unwrap_or_else(|_| "/sys/fs/cgroup/fuzzer_compare".to_string())
+});
+
+/// Squirrel running envs for per database, campaign paths are added by the runner
 pub static SQUIRREL_ENVS_TABLE: Lazy<HashMap<&'static str, HashMap<&'static str, String>>> =
     Lazy::new(|| {
         let envs = vec![
@@ -74,21 +143,6 @@ pub static SQUIRREL_ENVS_TABLE: Lazy<HashMap<&'static str, HashMap<&'static str,
                         "SQUIRREL_CONFIG",
                         format!("{}/squirrel/config_postgresql.yml", CONFIG_DIR.as_str()),
                     ),
-                    (
-                        "TEST_PATH",
-                        format!("{}/squirrel-pg/test", TEST_DIR.as_str()),
-                    ),
-                    (
-                        "PGSQL_INSTALL_PATH",
-                        format!("{}/postgresql", INSTALL_DIR.as_str()),
-                    ),
-                    (
-                        "LLVM_PROFILE_FILE",
-                        format!(
-                            "{}/squirrel-pg/prof/squirrel-postgresql-%p-%m.profraw",
-                            TEST_DIR.as_str()
-                        ),
-                    ),
                 ],
             ),
             (
@@ -114,21 +168,6 @@ pub static SQUIRREL_ENVS_TABLE: Lazy<HashMap<&'static str, HashMap<&'static str,
                         "SQUIRREL_CONFIG",
                         format!("{}/squirrel/config_mysql.yml", CONFIG_DIR.as_str()),
                     ),
-                    (
-                        "TEST_PATH",
-                        format!("{}/squirrel-my/test", TEST_DIR.as_str()),
-                    ),
-                    (
-                        "MYSQL_INSTALL_PATH",
-                        format!("{}/mysql", INSTALL_DIR.as_str()),
-                    ),
-                    (
-                        "LLVM_PROFILE_FILE",
-                        format!(
-                            "{}/squirrel-my/prof/squirrel-mysql-%p-%m.profraw",
-                            TEST_DIR.as_str()
-                        ),
-                    ),
                 ],
             ),
             (
@@ -154,28 +193,38 @@ pub static SQUIRREL_ENVS_TABLE: Lazy<HashMap<&'static str, HashMap<&'static str,
                         "SQUIRREL_CONFIG",
                         format!("{}/squirrel/config_mariadb.yml", CONFIG_DIR.as_str()),
                     ),
+                ],
+            ),
+            (
+                "sqlite",
+                vec![
+                    ("AFL_CUSTOM_MUTATOR_ONLY", "1".to_string()),
                     (
-                        "TEST_PATH",
-                        format!("{}/squirrel-ma/test", TEST_DIR.as_str()),
-                    ),
-                    (
-                        "MARIADB_INSTALL_PATH",
-                        format!("{}/mariadb", INSTALL_DIR.as_str()),
-                    ),
-                    (
-                        "LLVM_PROFILE_FILE",
+                        "AFL_CUSTOM_MUTATOR_LIBRARY",
                         format!(
-                            "{}/squirrel-ma/prof/squirrel-mariadb-%p-%m.profraw",
-                            TEST_DIR.as_str()
+                            "{}/squirrel/build/libsqlite_mutator.so",
+                            FUZZERS_DIR.as_str()
                         ),
                     ),
+                    ("AFL_IGNORE_PROBLEMS", "1".to_string()),
+                    ("AFL_DISABLE_TRIM", "1".to_string()),
+                    ("AFL_MAP_SIZE", "2097152".to_string()),
+                    ("AFL_I_DONT_CARE_ABOUT_MISSING_CRASHES", "1".to_string()),
+                    ("AFL_AUTORESUME", "1".to_string()),
+                    ("AFL_DEBUG", "1".to_string()),
+                    ("AFL_NO_UI", "1".to_string()),
+                    ("AFL_FORKSRV_INIT_TMOUT", "1000000".to_string()),
+                    (
+                        "SQUIRREL_CONFIG",
+                        format!("{}/squirrel/config_sqlite.yml", CONFIG_DIR.as_str()),
+                    ),
                 ],
             ),
         ];
 
         envs.into_iter()
             .map(|(s, m)| {
-                let tmp: HashMap<&str, String> = m.into_iter().map(|(k, v)| (k, v)).collect();
+                let tmp: HashMap<&str, String> = m.into_iter().collect();
                 (s, tmp)
             })
             .collect()
@@ -0,0 +1,204 @@
+use std::{
+    io::Write,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    campaign::{self, Campaign},
+    fuzzer::FuzzersConfig,
+    monitor::{ServerMonitor, StatusMonitor},
+    queue::Queue,
+    report, status_source,
+};
+
+/// Coverage columns of the monitor CSV shown from its last sample
+const COVERAGE_COLUMNS: [&str; 2] = ["line_cov_percent", "branch_cov_percent"];
+
+const HEADER: [&str; 11] = [
+    "campaign",
+    "health",
+    "execs/s",
+    "edges",
+    "crashes",
+    "hangs",
+    "last find",
+    "line %",
+    "branch %",
+    "server",
+    "remaining",
+];
+
+/// Print the progress of every campaign of `run`, or of every running run.
+///
+/// With `watch`, the screen is redrawn at that interval until interrupted.
+pub fn status(
+    run: Option<&str>,
+    fuzzers: &FuzzersConfig,
+    watch: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(interval) = watch else {
+        print!("{}", render(run, fuzzers)?);
+        return Ok(());
+    };
+
+    loop {
+        let frame = render(run, fuzzers)?;
+        // Clear the screen and home the cursor before every frame.
+        print!("\x1b[2J\x1b[H{}", frame);
+        std::io::stdout().flush()?;
+        std::thread::sleep(interval);
+    }
+}
+
+/// One table per run, read from the same files the monitors sample
+fn render(
+    run: Option<&str>,
+    fuzzers: &FuzzersConfig,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let runs = match run {
+        Some(run) => vec![campaign::resolve(run)?],
+        None => {
+            let running = campaign::runs()
+                .into_iter()
+                .filter(|r| campaign::running_pid(r).is_some())
+                .collect::<Vec<_>>();
+            if running.is_empty() {
+                campaign::resolve("latest").into_iter().collect()
+            } else {
+                running
+            }
+        }
+    };
+
+    let mut out = String::new();
+    for run in runs {
+        let running = campaign::running_pid(&run);
+        let state = match running {
+            Some(pid) => format!("running as pid {}", pid),
+            None => "stopped".to_string(),
+        };
+        out += &format!("{} ({})\n", run, state);
+        if let Ok(queue) = Queue::load(&run) {
+            out += &format!("  jobs: {}\n", queue.progress());
+        }
+
+        let mut rows = vec![HEADER.iter().map(|h| h.to_string()).collect()];
+        for c in campaign::load(&run)? {
+            rows.push(row(&c, fuzzers, running.is_some()));
+        }
+        out += &table(&rows);
+        out += "\n";
+    }
+
+    Ok(out)
+}
+
+fn row(c: &Campaign, fuzzers: &FuzzersConfig, running: bool) -> Vec<String> {
+    let (status_file, status_kind) = status_source(c, fuzzers);
+    let (status, _) = StatusMonitor::new(status_kind, status_file).get_result();
+    let coverage = report::last_sample(&c.output_csv(), &COVERAGE_COLUMNS)
+        .ok()
+        .flatten()
+        .unwrap_or(vec![-1.0; COVERAGE_COLUMNS.len()]);
+    // Judged by the watchdog of the running campaign, as of its last sample.
+    let health = report::last_row(&c.output_csv(), &["health"])
+        .ok()
+        .flatten()
+        .and_then(|row| row[0].clone())
+        .unwrap_or("-".to_string());
+    let now = SystemTime::now();
+
+    let last_find = match status.last_find {
+        0 => "none yet".to_string(),
+        t if t > 0 => {
+            let found = UNIX_EPOCH + Duration::from_secs(t as u64);
+            let age = now.duration_since(found).unwrap_or_default();
+            format!("{} ago", format_secs(age.as_secs()))
+        }
+        _ => "-".to_string(),
+    };
+
+    let remaining = match (c.duration(), c.started()) {
+        (Some(duration), Some(started)) if running => {
+            let elapsed = now.duration_since(started).unwrap_or_default();
+            format_secs(duration.saturating_sub(elapsed).as_secs())
+        }
+        (None, _) if running => "unbounded".to_string(),
+        _ => "-".to_string(),
+    };
+
+    vec![
+        c.id(),
+        health,
+        known(status.execs_per_sec, format!("{:.1}", status.execs_per_sec)),
+        known(status.edge_covered, status.edge_covered.to_string()),
+        known(status.crash_num, status.crash_num.to_string()),
+        known(status.hang_num, status.hang_num.to_string()),
+        last_find,
+        known(coverage[0], format!("{:.2}", coverage[0])),
+        known(coverage[1], format!("{:.2}", coverage[1])),
+        server_health(c),
+        remaining,
+    ]
+}
+
+/// `shown`, or `-` for a stat the fuzzer does not report
+fn known<T: PartialOrd + Default>(value: T, shown: String) -> String {
+    if value < T::default() {
+        "-".to_string()
+    } else {
+        shown
+    }
+}
+
+/// Whether the database server of `c` accepts connections on its port
+fn server_health(c: &Campaign) -> String {
+    let server = ServerMonitor::new(format!("{}/resources.json", c.dir())).get_result();
+    match server.port {
+        Some(port) if server.up => format!("up :{}", port),
+        Some(port) => format!("down :{}", port),
+        // SQLite runs inside the harness.
+        None => "-".to_string(),
+    }
+}
+
+/// Seconds as e.g. `45s`, `12m05s` or `3h02m`
+fn format_secs(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 60 * 60 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+/// Left-aligned columns, each as wide as its widest cell
+fn table(rows: &[Vec<String>]) -> String {
+    let widths = (0..HEADER.len())
+        .map(|i| rows.iter().map(|r| r[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    rows.iter()
+        .map(|r| {
+            let cells = r
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            format!("  {}\n", cells.join("  ").trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_secs() {
+        assert_eq!(format_secs(45), "45s");
+        assert_eq!(format_secs(725), "12m05s");
+        assert_eq!(format_secs(3 * 3600 + 150), "3h02m");
+        assert_eq!(known(-1, "x".to_string()), "-");
+        assert_eq!(known(0.5, "0.5".to_string()), "0.5");
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    constants::FUZZERS_DIR,
+    monitor::StatusKind,
+    source::{Source, SourceKind},
+    CAMPAIGN_TARGETS,
+};
+
+/// Fuzzers defined entirely in `config/fuzzers.toml`, besides the built-in runners
+#[derive(Deserialize, Default, Clone)]
+pub struct FuzzersConfig {
+    #[serde(flatten)]
+    fuzzers: BTreeMap<String, FuzzerConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FuzzerConfig {
+    /// Git repository checked out into `fuzzers/<name>` through the source cache
+    pub git: Option<String>,
+
+    /// Git tag/commit of `git`, pinned by the source manifest
+    #[serde(default = "default_rev")]
+    pub rev: String,
+
+    /// Shell commands building the fuzzer, run in order inside `fuzzers/<name>`
+    #[serde(default)]
+    pub build: Vec<String>,
+
+    /// Targets to fuzz, e.g. `postgresql`
+    pub targets: Vec<String>,
+
+    /// Launch command with `{input_dir}`, `{output_dir}`, `{target_bin}` and `{port}` placeholders
+    pub launch: String,
+
+    /// Seed directory, Squirrel's seeds of the target by default
+    pub input_dir: Option<String>,
+
+    /// Environment of the launch command
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Format of the stats file the fuzzer writes
+    #[serde(default)]
+    pub stats: StatusKind,
+
+    /// Stats file relative to `{output_dir}`
+    pub stats_file: Option<String>,
+
+    /// Queue directory relative to `{output_dir}`, next to an AFL stats file by default
+    pub queue_dir: Option<String>,
+
+    /// Start the target server on `{port}` before launching, otherwise only initialize its data
+    #[serde(default)]
+    pub start_server: bool,
+}
+
+fn default_rev() -> String {
+    "HEAD".to_string()
+}
+
+impl FuzzersConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Self = toml::from_str(content)?;
+        for (name, fuzzer) in &config.fuzzers {
+            if matches!(name.as_str(), "squirrel" | "aflpp" | "sqlsmith") {
+                return Err(format!("Fuzzer {} is built in", name).into());
+            }
+            for target in &fuzzer.targets {
+                if !CAMPAIGN_TARGETS.iter().any(|(_, t, _)| t == target) {
+                    return Err(format!("Unknown target {} of fuzzer {}", target, name).into());
+                }
+                if fuzzer.start_server && target != "postgresql" {
+                    return Err(format!(
+                        "Fuzzer {} can only have a PostgreSQL server started for it",
+                        name
+                    )
+                    .into());
+                }
+            }
+            if fuzzer.stats != StatusKind::None && fuzzer.stats_file.is_none() {
+                return Err(format!("No stats_file configured for fuzzer {}", name).into());
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FuzzerConfig> {
+        self.fuzzers.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &FuzzerConfig)> {
+        self.fuzzers.iter()
+    }
+}
+
+impl FuzzerConfig {
+    /// Queue directory relative to `{output_dir}`, if the fuzzer keeps one
+    pub fn queue_dir(&self) -> Option<String> {
+        if self.queue_dir.is_some() {
+            return self.queue_dir.clone();
+        }
+        if self.stats != StatusKind::Afl {
+            return None;
+        }
+
+        let stats_file = Path::new(self.stats_file.as_deref()?);
+        Some(stats_file.with_file_name("queue").display().to_string())
+    }
+
+    /// Source of the fuzzer named `name`, if it is fetched rather than built in place
+    pub fn source(&self, name: &str) -> Option<Source> {
+        self.git.as_ref().map(|url| Source {
+            name: name.to_string(),
+            version: self.rev.clone(),
+            kind: SourceKind::Git {
+                url: url.clone(),
+                reference: self.rev.clone(),
+            },
+        })
+    }
+}
+
+/// Directory the fuzzer named `name` is checked out, built and launched in
+pub fn fuzzer_dir(name: &str) -> String {
+    format!("{}/{}", FUZZERS_DIR.as_str(), name)
+}
+
+/// Substitute every `{placeholder}` of `template`.
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = template.to_string();
+    for (key, value) in values {
+        rendered = rendered.replace(&format!("{{{}}}", key), value);
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzers_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config = FuzzersConfig::parse(
+            r#"
+            [griffin]
+            git = "https://example.com/griffin.git"
+            build = ["make"]
+            targets = ["postgresql", "sqlite"]
+            launch = "./afl-fuzz -i {input_dir} -o {output_dir} -- {target_bin}"
+            stats = "afl"
+            stats_file = "default/fuzzer_stats"
+            "#,
+        )?;
+
+        let griffin = config.get("griffin").unwrap();
+        assert_eq!(griffin.rev, "HEAD");
+        assert!(griffin.stats == StatusKind::Afl);
+        assert_eq!(griffin.source("griffin").unwrap().key(), "griffin@HEAD");
+        assert_eq!(griffin.queue_dir().unwrap(), "default/queue");
+
+        assert!(FuzzersConfig::parse("[lego]\ntargets = [\"oracle\"]\nlaunch = \"\"").is_err());
+        assert!(FuzzersConfig::parse(
+            "[lego]\ntargets = [\"mysql\"]\nlaunch = \"\"\nstats = \"json\""
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() {
+        let cmd = render(
+            "fuzz -o {output_dir} -p {port} {port}",
+            &[("output_dir", "/tmp/out"), ("port", "5434")],
+        );
+        assert_eq!(cmd, "fuzz -o /tmp/out -p 5434 5434");
+    }
+}
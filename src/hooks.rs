@@ -0,0 +1,415 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Names of the events a hook can subscribe to
+pub const EVENTS: [&str; 4] = ["crash", "hang", "stall", "server_down"];
+
+/// Timeout of connecting to and talking with a hook endpoint
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Actions run on campaign events, read from `config/hooks.toml`
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Seconds without new executions before a campaign counts as stalled
+    pub stall_secs: Option<i64>,
+
+    #[serde(default, rename = "hook")]
+    pub hooks: Vec<Hook>,
+}
+
+/// One action, run for the listed events or for all of them if none are listed
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// Shell command getting the event as JSON on stdin
+    pub command: Option<String>,
+
+    /// File the event is appended to as a JSON line
+    pub file: Option<String>,
+
+    /// `http://` endpoint the event is POSTed to as JSON
+    pub url: Option<String>,
+}
+
+/// Something worth knowing right away that happened to a campaign
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Event {
+    pub run: String,
+    pub campaign: String,
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Crash { count: i64, previous: i64 },
+    Hang { count: i64, previous: i64 },
+    Stall { execs: i64, idle_secs: i64 },
+    ServerDown { port: Option<u16> },
+}
+
+/// Stats of one sample the events are derived from, -1 where unknown
+pub struct Sample {
+    pub crash_num: i64,
+    pub hang_num: i64,
+    pub execs: i64,
+    pub server_up: Option<bool>,
+    pub port: Option<u16>,
+}
+
+/// What the previous samples of a campaign showed, turning new samples into events
+#[derive(Default)]
+pub struct Tracker {
+    crash_num: i64,
+    hang_num: i64,
+    execs: i64,
+    /// Unix time the executions last advanced, absent before they were first known
+    progressed_at: Option<i64>,
+    stalled: bool,
+    server_up: Option<bool>,
+}
+
+impl HooksConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        let config: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        for hook in &config.hooks {
+            hook.validate()?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn stall_secs(&self) -> i64 {
+        self.stall_secs.unwrap_or(600)
+    }
+
+    /// Run every hook subscribed to `event` in the background, reporting failures on stderr.
+    pub fn fire(&self, event: &Event) {
+        let payload = match serde_json::to_string(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("Cannot serialize event of {}: {}", event.campaign, e);
+                return;
+            }
+        };
+
+        for hook in self.hooks.iter().filter(|h| h.wants(event.kind.name())) {
+            let hook = hook.clone();
+            let payload = payload.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = hook.run(&payload) {
+                    eprintln!("Hook {} failed: {}", hook.action(), e);
+                }
+            });
+        }
+    }
+}
+
+impl Hook {
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let actions = [&self.command, &self.file, &self.url]
+            .iter()
+            .filter(|a| a.is_some())
+            .count();
+        if actions != 1 {
+            return Err("Every hook needs exactly one of command, file and url".into());
+        }
+        if let Some(event) = self.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(
+                format!("Unknown hook event {}, expected one of {:?}", event, EVENTS).into(),
+            );
+        }
+        if let Some(url) = &self.url {
+            split_url(url)?;
+        }
+
+        Ok(())
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+
+    fn action(&self) -> &str {
+        self.command
+            .as_deref()
+            .or(self.file.as_deref())
+            .or(self.url.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Hand the JSON `payload` to the action of the hook.
+    pub fn run(&self, payload: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(command) = &self.command {
+            let mut child = Command::new("/bin/sh")
+                .args(["-c", command])
+                .stdin(Stdio::piped())
+                .spawn()?;
+            child
+                .stdin
+                .take()
+                .ok_or("No stdin")?
+                .write_all(payload.as_bytes())?;
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(format!("exited with {}", status).into());
+            }
+        } else if let Some(file) = &self.file {
+            let mut f = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(file)?;
+            writeln!(f, "{}", payload)?;
+        } else if let Some(url) = &self.url {
+            post(url, payload)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Crash { .. } => "crash",
+            Self::Hang { .. } => "hang",
+            Self::Stall { .. } => "stall",
+            Self::ServerDown { .. } => "server_down",
+        }
+    }
+}
+
+impl Tracker {
+    /// Events between the previous samples and `sample` taken at unix time `now`.
+    ///
+    /// Unknown stats are skipped, so a fuzzer that has not written its stats yet stays quiet.
+    pub fn update(&mut self, sample: &Sample, now: i64, stall_secs: i64) -> Vec<EventKind> {
+        let mut events = vec![];
+
+        if sample.crash_num > self.crash_num {
+            events.push(EventKind::Crash {
+                count: sample.crash_num,
+                previous: self.crash_num,
+            });
+            self.crash_num = sample.crash_num;
+        }
+        if sample.hang_num > self.hang_num {
+            events.push(EventKind::Hang {
+                count: sample.hang_num,
+                previous: self.hang_num,
+            });
+            self.hang_num = sample.hang_num;
+        }
+
+        if sample.execs >= 0 {
+            if self.progressed_at.is_none() || sample.execs > self.execs {
+                self.execs = sample.execs;
+                self.progressed_at = Some(now);
+                self.stalled = false;
+            }
+            let idle_secs = now - self.progressed_at.unwrap_or(now);
+            if !self.stalled && idle_secs >= stall_secs {
+                events.push(EventKind::Stall {
+                    execs: self.execs,
+                    idle_secs,
+                });
+                self.stalled = true;
+            }
+        }
+
+        if self.server_up == Some(true) && sample.server_up == Some(false) {
+            events.push(EventKind::ServerDown { port: sample.port });
+        }
+        if sample.server_up.is_some() {
+            self.server_up = sample.server_up;
+        }
+
+        events
+    }
+}
+
+/// Host with port and path of an `http://` URL
+fn split_url(url: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Hook url {} must start with http://", url))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(format!("Hook url {} has no host", url).into());
+    }
+    let host = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+
+    Ok((host, path.to_string()))
+}
+
+/// POST `body` as JSON to `url`, failing unless the endpoint answers with 2xx.
+fn post(url: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (host, path) = split_url(url)?;
+    let addr = host
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status = response.split_whitespace().nth(1).ok_or("Empty response")?;
+    if !status.starts_with('2') {
+        return Err(format!("{} answered {}", url, status).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_hooks_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config: HooksConfig = toml::from_str(
+            "stall_secs = 60\n\
+             [[hook]]\n\
+             events = [\"crash\"]\n\
+             file = \"notifications.jsonl\"\n",
+        )?;
+        assert_eq!(config.stall_secs(), 60);
+        assert!(config.hooks[0].validate().is_ok());
+        assert!(config.hooks[0].wants("crash"));
+        assert!(!config.hooks[0].wants("hang"));
+
+        let both: Hook = toml::from_str("file = \"a\"\nurl = \"http://localhost/\"")?;
+        assert!(both.validate().is_err());
+        let https: Hook = toml::from_str("url = \"https://example.com/\"")?;
+        assert!(https.validate().is_err());
+        let unknown: Hook = toml::from_str("events = [\"oops\"]\nfile = \"a\"")?;
+        assert!(unknown.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tracker_events() {
+        let sample = |crash_num, hang_num, execs, server_up| Sample {
+            crash_num,
+            hang_num,
+            execs,
+            server_up,
+            port: Some(5433),
+        };
+        let mut tracker = Tracker::default();
+
+        assert!(tracker.update(&sample(-1, -1, -1, None), 0, 600).is_empty());
+        assert!(tracker
+            .update(&sample(0, 0, 100, Some(true)), 30, 600)
+            .is_empty());
+        assert_eq!(
+            tracker.update(&sample(2, 0, 200, Some(true)), 60, 600),
+            vec![EventKind::Crash {
+                count: 2,
+                previous: 0
+            }]
+        );
+        assert!(tracker
+            .update(&sample(2, 0, 200, Some(true)), 600, 600)
+            .is_empty());
+        assert_eq!(
+            tracker.update(&sample(2, 1, 200, Some(false)), 660, 600),
+            vec![
+                EventKind::Hang {
+                    count: 1,
+                    previous: 0
+                },
+                EventKind::Stall {
+                    execs: 200,
+                    idle_secs: 600
+                },
+                EventKind::ServerDown { port: Some(5433) },
+            ]
+        );
+        // Each stall and outage is reported once.
+        assert!(tracker
+            .update(&sample(2, 1, 200, Some(false)), 900, 600)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_post_hook() -> Result<(), Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/notify", listener.local_addr()?);
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 4096];
+            let mut len = 0;
+            while !String::from_utf8_lossy(&request[..len]).ends_with("}") {
+                len += stream.read(&mut request[len..]).unwrap();
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&request[..len]).to_string()
+        });
+
+        let hook = Hook {
+            events: vec![],
+            command: None,
+            file: None,
+            url: Some(url),
+        };
+        let event = Event {
+            run: "20240131-093000-hooks".to_string(),
+            campaign: "squirrel-sqlite-trial1".to_string(),
+            timestamp: "2024-01-31 09:35:00".to_string(),
+            kind: EventKind::Crash {
+                count: 1,
+                previous: 0,
+            },
+        };
+        hook.run(&serde_json::to_string(&event)?)?;
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /notify HTTP/1.1\r\n"));
+        assert!(request.contains("\"event\":\"crash\""));
+        assert!(request.contains("\"campaign\":\"squirrel-sqlite-trial1\""));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Timeout of reading a request from and writing a response to a client
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answer to a GET request
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self {
+            status: 404,
+            content_type: "text/plain; charset=utf-8",
+            body: b"Not found\n".to_vec(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Answer the GET requests to `addr` with `handler`, given the requested path
+/// and query, from a background thread per client.
+///
+/// Returns the address bound, so port 0 picks a free one.
+pub fn serve<F>(addr: &str, handler: F) -> Result<SocketAddr, Box<dyn std::error::Error>>
+where
+    F: Fn(&str) -> Response + Send + Sync + 'static,
+{
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Cannot listen on {}: {}", addr, e))?;
+    let bound = listener.local_addr()?;
+    let handler = Arc::new(handler);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = handler.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = answer(stream, handler.as_ref()) {
+                    eprintln!("Cannot answer a request on {}: {}", bound, e);
+                }
+            });
+        }
+    });
+
+    Ok(bound)
+}
+
+fn answer(
+    stream: TcpStream,
+    handler: &dyn Fn(&str) -> Response,
+) -> Result<(), Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Requests without a body end at the first empty line.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => handler(target),
+        (Some(_), Some(_)) => Response {
+            status: 405,
+            content_type: "text/plain; charset=utf-8",
+            body: b"Only GET is supported\n".to_vec(),
+        },
+        _ => Response {
+            status: 400,
+            content_type: "text/plain; charset=utf-8",
+            body: b"Malformed request\n".to_vec(),
+        },
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_serve() -> Result<(), Box<dyn std::error::Error>> {
+        let addr = serve("127.0.0.1:0", |target| match target {
+            "/hello" => Response::ok("text/plain", "hi"),
+            _ => Response::not_found(),
+        })?;
+
+        let get = |request: &str| -> Result<String, Box<dyn std::error::Error>> {
+            let mut stream = TcpStream::connect(addr)?;
+            stream.write_all(request.as_bytes())?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+            Ok(response)
+        };
+
+        let hello = get("GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        assert!(hello.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(hello.ends_with("\r\n\r\nhi"));
+        assert!(get("GET /missing HTTP/1.1\r\n\r\n")?.starts_with("HTTP/1.1 404"));
+        assert!(get("POST /hello HTTP/1.1\r\n\r\n")?.starts_with("HTTP/1.1 405"));
+
+        Ok(())
+    }
+}
@@ -1,120 +1,654 @@
+pub mod campaign;
 pub mod constants;
+pub mod fuzzer;
+pub mod hooks;
+pub mod http;
+pub mod manifest;
+pub mod queue;
+pub mod source;
+pub mod target;
 pub mod utils;
 
 pub mod builder;
+pub mod dashboard;
+pub mod metrics;
 pub mod monitor;
+pub mod report;
 pub mod runner;
+pub mod web;
 
-use std::thread::JoinHandle;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use campaign::Campaign;
 
-use builder::{BoostBuilder, Builder, MariadbBuilder, MysqlBuilder, PgsqlBuilder, SquirrelBuilder};
+use builder::{
+    BoostBuilder, Builder, CustomBuilder, MariadbBuilder, MysqlBuilder, PgsqlBuilder,
+    SqliteBuilder, SqlsmithBuilder, SquirrelBuilder,
+};
 use constants::{
-    CONFIG_DIR, DB_DIR, FUZZERS_DIR, INSTALL_DIR, OUTPUT_DIR, ROOT, TEST_DIR, TMP_DIR,
+    CACHE_DIR, COLLECTORS_CONFIG, CONFIG_DIR, DB_DIR, FUZZERS_DIR, HISTORY_DB, HOOKS_CONFIG,
+    INSTALL_DIR, ROOT, TMP_DIR, WATCHDOG_CONFIG,
+};
+use fuzzer::FuzzersConfig;
+use hooks::HooksConfig;
+use monitor::{
+    CollectorConfig, CollectorsConfig, MonitorManager, MonitorManagerConfig, RestartFn, SinkConfig,
+    SinkKind, StatusKind, WatchdogConfig,
 };
-use monitor::{MonitorManager, MonitorManagerConfig};
-use runner::{Runner, SquirrelRunner};
+use queue::Queue;
+use runner::{AflppRunner, CustomRunner, Runner, SqlsmithRunner, SquirrelRunner};
+use target::TargetsConfig;
 use utils::ShellCommand;
 
 use clokwerk::{Scheduler, TimeUnits};
 
+/// Shortest rest of a time budget worth restarting a fuzzer for
+const MIN_RESTART_BUDGET: Duration = Duration::from_secs(60);
+
 fn pre_setup() -> Result<(), Box<dyn std::error::Error>> {
     utils::create_dir(TMP_DIR.as_str())?;
     utils::create_dir(DB_DIR.as_str())?;
     utils::create_dir(FUZZERS_DIR.as_str())?;
     utils::create_dir(INSTALL_DIR.as_str())?;
     utils::create_dir(CONFIG_DIR.as_str())?;
-    utils::create_new_dir(TEST_DIR.as_str())?;
-    utils::create_new_dir(OUTPUT_DIR.as_str())?;
 
     Ok(())
 }
 
 fn post_setup() -> Result<(), Box<dyn std::error::Error>> {
-    let mv_config_cmd = [
+    copy_squirrel_configs(
+        ROOT.as_str(),
+        CONFIG_DIR.as_str(),
+        &utils::log_file("setup", "config", "copy"),
+    )
+}
+
+/// Copy the Squirrel configs of `<root>/config/squirrel` to `<config_dir>/squirrel`,
+/// where the runners fill in the port and data directory of each campaign.
+fn copy_squirrel_configs(
+    root: &str,
+    config_dir: &str,
+    log_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cp_config_cmd = [
         "cp".to_string(),
-        format!("{}/squirrel/", ROOT.as_str()),
-        CONFIG_DIR.to_string(),
+        "-r".to_string(),
+        format!("{}/config/squirrel", root),
+        format!("{}/", config_dir),
     ];
 
     ShellCommand::new()
-        .args(&mv_config_cmd)
-        .current_dir(ROOT.as_str())
-        .spawn()?
-        .wait_with_output()?;
+        .args(&cp_config_cmd)
+        .current_dir(root)
+        .log_to(log_file)
+        .run()?;
 
     Ok(())
 }
 
-pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
+/// Names `setup` accepts besides the fuzzers of `config/fuzzers.toml`
+const SETUP_NAMES: [&str; 7] = [
+    "squirrel",
+    "sqlsmith",
+    "postgresql",
+    "boost",
+    "mysql",
+    "mariadb",
+    "sqlite",
+];
+
+/// Build the named targets and fuzzers, or everything if `only` is empty.
+///
+/// Squirrel is always built, since every target is compiled with its AFL++.
+pub fn setup(
+    targets: &TargetsConfig,
+    fuzzers: &FuzzersConfig,
+    only: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for name in only {
+        if !SETUP_NAMES.contains(&name.as_str()) && fuzzers.get(name).is_none() {
+            return Err(format!("Unknown target or fuzzer {}", name).into());
+        }
+    }
+    let wanted = |name: &str| only.is_empty() || only.iter().any(|o| o == name);
+
     pre_setup()?;
 
-    SquirrelBuilder::setup()?;
-    PgsqlBuilder::setup()?;
-    BoostBuilder::setup()?;
-    MysqlBuilder::setup()?;
-    MariadbBuilder::setup()?;
+    SquirrelBuilder::setup(targets)?;
+    if wanted("postgresql") {
+        PgsqlBuilder::setup(targets)?;
+    }
+    if wanted("boost") || wanted("mysql") {
+        BoostBuilder::setup(targets)?;
+    }
+    if wanted("mysql") {
+        MysqlBuilder::setup(targets)?;
+    }
+    if wanted("mariadb") {
+        MariadbBuilder::setup(targets)?;
+    }
+    if wanted("sqlite") {
+        SqliteBuilder::setup(targets)?;
+    }
+    if wanted("sqlsmith") {
+        SqlsmithBuilder::setup(targets)?;
+    }
+    for (name, fuzzer) in fuzzers.iter() {
+        if wanted(name) {
+            CustomBuilder::setup(name, fuzzer, targets.forced(name))?;
+        }
+    }
 
     post_setup()?;
 
     Ok(())
 }
 
-pub fn setup_monitor() -> Vec<MonitorManager> {
-    let pg_monitor_config = MonitorManagerConfig::new(
-        format!("{}/squirrel-pg.csv", OUTPUT_DIR.as_str()),
-        format!("{}/squirrel-pg/test/1/fuzzer_stats", TEST_DIR.as_str()),
-        format!("{}/postgresql/bin/postgres", INSTALL_DIR.as_str()),
-        format!("{}/squirrel-pg/prof", TEST_DIR.as_str()),
-    );
+/// Populate the source cache for an offline setup later on.
+pub fn fetch(
+    targets: &TargetsConfig,
+    fuzzers: &FuzzersConfig,
+    pin: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    utils::create_dir(CACHE_DIR.as_str())?;
+    source::fetch_all(targets, fuzzers, pin)
+}
 
-    let pg_monitor = MonitorManager::new("squirrel-pg".to_string(), pg_monitor_config);
+/// Campaign suffix, target and coverage-instrumented binary of every target
+pub(crate) const CAMPAIGN_TARGETS: [(&str, &str, &str); 4] = [
+    ("pg", "postgresql", "bin/postgres"),
+    ("my", "mysql", "bin/mysqld"),
+    ("ma", "mariadb", "bin/mariadbd"),
+    ("sl", "sqlite", "bin/ossfuzz"),
+];
 
-    let my_monitor_config = MonitorManagerConfig::new(
-        format!("{}/squirrel-my.csv", OUTPUT_DIR.as_str()),
-        format!("{}/squirrel-my/test/1/fuzzer_stats", TEST_DIR.as_str()),
-        format!("{}/mysql/bin/mysqld", INSTALL_DIR.as_str()),
-        format!("{}/squirrel-my/prof", TEST_DIR.as_str()),
-    );
+/// Built-in fuzzers with the targets they support
+const FUZZERS: [(&str, &[&str]); 3] = [
+    ("squirrel", &["postgresql", "mysql", "mariadb", "sqlite"]),
+    ("aflpp", &["postgresql", "mysql", "mariadb", "sqlite"]),
+    ("sqlsmith", &["postgresql"]),
+];
 
-    let my_monitor = MonitorManager::new("squirrel-my".to_string(), my_monitor_config);
+/// What `run` launches, every fuzzer and target by default
+pub struct RunOptions {
+    pub trials: u32,
+    /// Time budget of every campaign, unbounded if absent
+    pub duration: Option<Duration>,
+    pub targets: Vec<String>,
+    pub fuzzers: Vec<String>,
+    /// Sinks every sample is written to besides the monitor CSV
+    pub sinks: Vec<SinkKind>,
+    /// Address to export the samples on for Prometheus, e.g. `127.0.0.1:9184`
+    pub metrics: Option<String>,
+}
 
-    let ma_monitor_config = MonitorManagerConfig::new(
-        format!("{}/squirrel-ma.csv", OUTPUT_DIR.as_str()),
-        format!("{}/squirrel-ma/test/1/fuzzer_stats", TEST_DIR.as_str()),
-        format!("{}/mariadb/bin/mariadbd", INSTALL_DIR.as_str()),
-        format!("{}/squirrel-ma/prof", TEST_DIR.as_str()),
-    );
+/// Campaigns of `run`: every trial of each chosen fuzzer against each chosen target it supports
+pub fn plan(
+    run: &str,
+    targets: &TargetsConfig,
+    fuzzers: &FuzzersConfig,
+    options: &RunOptions,
+) -> Result<Vec<Campaign>, Box<dyn std::error::Error>> {
+    let experiment = campaign::experiment_of(run).ok_or_else(|| format!("Invalid run {}", run))?;
+    let mut supported: Vec<(String, Vec<String>)> = FUZZERS
+        .iter()
+        .map(|(f, ts)| (f.to_string(), ts.iter().map(|t| t.to_string()).collect()))
+        .collect();
+    supported.extend(fuzzers.iter().map(|(f, c)| (f.clone(), c.targets.clone())));
 
-    let ma_monitor = MonitorManager::new("squirrel-ma".to_string(), ma_monitor_config);
+    for name in &options.fuzzers {
+        if !supported.iter().any(|(f, _)| f == name) {
+            return Err(format!("Unknown fuzzer {}", name).into());
+        }
+    }
+    for name in &options.targets {
+        if !CAMPAIGN_TARGETS.iter().any(|(_, t, _)| t == name) {
+            return Err(format!("Unknown target {}", name).into());
+        }
+    }
 
-    vec![pg_monitor, my_monitor, ma_monitor]
+    let mut campaigns = vec![];
+    for (fuzzer, fuzzer_targets) in supported {
+        if !options.fuzzers.is_empty() && !options.fuzzers.contains(&fuzzer) {
+            continue;
+        }
+        for (_, target, _) in CAMPAIGN_TARGETS {
+            let target = target.to_string();
+            if !fuzzer_targets.contains(&target)
+                || (!options.targets.is_empty() && !options.targets.contains(&target))
+            {
+                continue;
+            }
+            for trial in 1..=options.trials {
+                campaigns.push(Campaign {
+                    experiment: experiment.to_string(),
+                    run: run.to_string(),
+                    fuzzer: fuzzer.clone(),
+                    target: target.clone(),
+                    version: targets.selected(&target),
+                    trial,
+                    duration_secs: options.duration.map(|d| d.as_secs()),
+                });
+            }
+        }
+    }
+    if campaigns.is_empty() {
+        return Err("No fuzzer supports the chosen targets".into());
+    }
+
+    Ok(campaigns)
+}
+
+/// Status file and its format of `campaign`
+pub(crate) fn status_source(campaign: &Campaign, fuzzers: &FuzzersConfig) -> (String, StatusKind) {
+    let test_dir = format!("{}/test", campaign.dir());
+    match campaign.fuzzer.as_str() {
+        "sqlsmith" => (format!("{}/sqlsmith_stats", test_dir), StatusKind::Sqlsmith),
+        name => match fuzzers.get(name) {
+            Some(fuzzer) => (
+                format!(
+                    "{}/{}",
+                    test_dir,
+                    fuzzer.stats_file.as_deref().unwrap_or_default()
+                ),
+                fuzzer.stats,
+            ),
+            None => (format!("{}/1/fuzzer_stats", test_dir), StatusKind::Afl),
+        },
+    }
 }
 
-pub fn monitor_loop(monitors: &mut Vec<MonitorManager>) {
-    for m in monitors {
-        m.dump_to_csv();
+/// Directory of the test cases a campaign keeps, if its fuzzer keeps any
+pub(crate) fn corpus_source(campaign: &Campaign, fuzzers: &FuzzersConfig) -> Option<String> {
+    let test_dir = format!("{}/test", campaign.dir());
+    match campaign.fuzzer.as_str() {
+        "sqlsmith" => None,
+        name => match fuzzers.get(name) {
+            Some(fuzzer) => Some(format!("{}/{}", test_dir, fuzzer.queue_dir()?)),
+            None => Some(format!("{}/1/queue", test_dir)),
+        },
     }
 }
 
-pub fn launch_monitors() {
-    let mut monitors = setup_monitor();
+/// What the monitors of a run sample and do with their samples besides the CSV
+#[derive(Clone)]
+pub struct Supervision {
+    pub collectors: Arc<CollectorsConfig>,
+    pub hooks: Arc<HooksConfig>,
+    pub watchdog: Arc<WatchdogConfig>,
+    pub sinks: Vec<SinkKind>,
+}
+
+impl Supervision {
+    /// Collectors, hooks and watchdog from their configs, with the sinks chosen for the run
+    pub fn load(sinks: &[SinkKind]) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            collectors: Arc::new(CollectorsConfig::load(COLLECTORS_CONFIG.as_str())?),
+            hooks: Arc::new(HooksConfig::load(HOOKS_CONFIG.as_str())?),
+            watchdog: Arc::new(WatchdogConfig::load(WATCHDOG_CONFIG.as_str())?),
+            sinks: sinks.to_vec(),
+        })
+    }
+}
+
+pub fn setup_monitor(
+    campaign: &Campaign,
+    fuzzers: &FuzzersConfig,
+    supervision: &Supervision,
+) -> Result<MonitorManager, Box<dyn std::error::Error>> {
+    let (status_file, status_kind) = status_source(campaign, fuzzers);
+    let killed = campaign.clone();
+    let restart: RestartFn = Arc::new(move |fuzzer_pid| {
+        if let Err(e) = campaign::kill(&killed, u32::try_from(fuzzer_pid).ok()) {
+            eprintln!("Cannot kill {} for its restart: {}", killed.id(), e);
+        }
+    });
+    let mut monitor_config = MonitorManagerConfig::new(campaign.output_csv(), status_file)
+        .status_kind(status_kind)
+        .label("fuzzer", campaign.fuzzer.clone())
+        .label("target", campaign.target.clone())
+        .label("trial", campaign.trial.to_string())
+        .resources_file(format!("{}/resources.json", campaign.dir()))
+        .hooks(supervision.hooks.clone(), campaign.run.clone())
+        .watchdog(supervision.watchdog.clone(), restart);
+    for collector in collectors(campaign, fuzzers, &supervision.collectors) {
+        monitor_config = monitor_config.collector(collector);
+    }
+    for sink in &supervision.sinks {
+        monitor_config = monitor_config.sink(match sink {
+            SinkKind::Jsonl => SinkConfig::Jsonl(format!("{}/monitor.jsonl", campaign.dir())),
+            SinkKind::Sqlite => SinkConfig::Sqlite {
+                db: HISTORY_DB.to_string(),
+                campaign: campaign.clone(),
+            },
+        });
+    }
+
+    MonitorManager::new(campaign.id(), monitor_config)
+}
+
+/// Collectors of `campaign` enabled by `config`, built-in ones first
+fn collectors(
+    campaign: &Campaign,
+    fuzzers: &FuzzersConfig,
+    config: &CollectorsConfig,
+) -> Vec<CollectorConfig> {
+    let mut collectors = vec![];
+    if config.has("stats") {
+        let (status_file, status_kind) = status_source(campaign, fuzzers);
+        collectors.push(CollectorConfig::Stats(status_kind, status_file));
+    }
+    if config.has("coverage") {
+        collectors.push(CollectorConfig::Coverage {
+            exec_path: campaign.target_bin(),
+            prof_dir: format!("{}/prof", campaign.dir()),
+        });
+    }
+    if config.has("resources") {
+        collectors.push(CollectorConfig::Resources(runner::cgroup::campaign_cgroup(
+            &campaign.key(),
+        )));
+    }
+    if config.has("disk") {
+        collectors.push(CollectorConfig::Disk(campaign.dir()));
+    }
+    if config.has("corpus") {
+        if let Some(queue_dir) = corpus_source(campaign, fuzzers) {
+            collectors.push(CollectorConfig::Corpus(queue_dir));
+        }
+    }
+    if config.has("validity") {
+        let queue_dir = corpus_source(campaign, fuzzers);
+        if let (Some(queue_dir), Some(reference)) =
+            (queue_dir, config.validity.reference(&campaign.target))
+        {
+            collectors.push(CollectorConfig::Validity {
+                queue_dir,
+                reference,
+                sample: config.validity.sample,
+                log_file: format!("{}/validity.jsonl", campaign.dir()),
+            });
+        }
+    }
+    for command in &config.command {
+        if command.applies(&campaign.fuzzer, &campaign.target) {
+            collectors.push(CollectorConfig::Command {
+                config: command.clone(),
+                dir: campaign.dir(),
+            });
+        }
+    }
+
+    collectors
+}
+
+/// Sample every monitored campaign.
+///
+/// Collectors may merge profiles or replay queues for minutes, so they run
+/// without the lock the watchdog checks and the metrics endpoint wait on.
+pub fn monitor_loop(monitors: &Monitors) {
+    let samplers = monitors
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|m| (m.name().to_string(), m.sampler()))
+        .collect::<Vec<_>>();
+
+    for (name, sampler) in samplers {
+        let metrics = sampler.sample();
+        // The campaign may have finished in the meantime, taking its last sample itself.
+        if let Some(m) = monitors
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|m| m.name() == name)
+        {
+            m.record(metrics);
+        }
+    }
+}
+
+/// Monitors of the running campaigns, sampled together
+pub type Monitors = Arc<Mutex<Vec<MonitorManager>>>;
+
+/// Sample every campaign added to the returned monitors every five minutes, from a background thread.
+///
+/// The cheap checks behind the watchdog and the hooks run every 30 seconds,
+/// so a crash or a stalled fuzzer is noticed right away.
+pub fn launch_monitors() -> Monitors {
+    let monitors = Monitors::default();
+    let sampled = monitors.clone();
+    let checked = monitors.clone();
     let mut scheduler = Scheduler::with_tz(chrono::Local);
 
     scheduler
         .every(5.minutes())
-        .run(move || monitor_loop(&mut monitors));
-}
-
-pub fn launch_fuzzers() -> Vec<JoinHandle<()>> {
-    let pg_handle = std::thread::spawn(|| {
-        let _res = SquirrelRunner::run_pgsql();
+        .run(move || monitor_loop(&sampled));
+    scheduler.every(30.seconds()).run(move || {
+        for m in checked.lock().unwrap().iter_mut() {
+            m.check();
+        }
     });
-    let ma_handle = std::thread::spawn(|| {
-        let _res = SquirrelRunner::run_mariadb();
+
+    std::thread::spawn(move || loop {
+        scheduler.run_pending();
+        std::thread::sleep(Duration::from_secs(1));
     });
-    let my_handle = std::thread::spawn(|| {
-        let _res = SquirrelRunner::run_mysql();
+
+    monitors
+}
+
+/// Run `campaign` to the end of its time budget, monitored for as long as it runs.
+///
+/// A fuzzer the watchdog killed for a restart is launched again for the rest of the budget.
+pub fn run_campaign(
+    campaign: &Campaign,
+    fuzzers: &FuzzersConfig,
+    supervision: &Supervision,
+    monitors: &Monitors,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let monitor = setup_monitor(campaign, fuzzers, supervision)?;
+    monitors.lock().unwrap().push(monitor);
+
+    let deadline = campaign.duration().map(|d| Instant::now() + d);
+    let mut res = launch(campaign, fuzzers);
+    while take_restart(monitors, campaign) {
+        let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        if remaining.is_some_and(|r| r < MIN_RESTART_BUDGET) {
+            break;
+        }
+
+        println!("Restarting {}", campaign.id());
+        let restarted = Campaign {
+            duration_secs: remaining.map(|r| r.as_secs()),
+            ..campaign.clone()
+        };
+        res = launch(&restarted, fuzzers);
+    }
+
+    // Stop sampling, then take a last sample now the fuzzer has exited.
+    let finished = {
+        let mut monitors = monitors.lock().unwrap();
+        monitors
+            .iter()
+            .position(|m| m.name() == campaign.id())
+            .map(|i| monitors.remove(i))
+    };
+    if let Some(mut monitor) = finished {
+        monitor.finish();
+    }
+    if let Err(e) = manifest::finish(campaign) {
+        eprintln!("Cannot record the end of {}: {}", campaign.id(), e);
+    }
+
+    res
+}
+
+/// Start the fuzzer of `campaign` through its runner, returning once it exits.
+fn launch(campaign: &Campaign, fuzzers: &FuzzersConfig) -> Result<(), Box<dyn std::error::Error>> {
+    match (campaign.fuzzer.as_str(), fuzzers.get(&campaign.fuzzer)) {
+        ("squirrel", _) => SquirrelRunner::run(campaign),
+        ("aflpp", _) => AflppRunner::run(campaign),
+        ("sqlsmith", _) => SqlsmithRunner::run_pgsql(campaign),
+        (_, Some(config)) => CustomRunner::run(config, campaign),
+        (fuzzer, None) => Err(format!("Unknown fuzzer {}", fuzzer).into()),
+    }
+}
+
+/// Whether the watchdog killed the fuzzer of `campaign` to restart it
+fn take_restart(monitors: &Monitors, campaign: &Campaign) -> bool {
+    monitors
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .find(|m| m.name() == campaign.id())
+        .is_some_and(|m| m.take_restart())
+}
+
+/// Run every campaign of a new run of `experiment`, queued within the core budget.
+pub fn run(
+    experiment: &str,
+    targets: &TargetsConfig,
+    fuzzers: &FuzzersConfig,
+    options: &RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run = campaign::new_run(experiment)?;
+    let campaigns = plan(&run, targets, fuzzers, options)?;
+    let queue = Queue {
+        sinks: options.sinks.clone(),
+        metrics: options.metrics.clone(),
+        ..Queue::new(&run, campaigns)
+    };
+    queue.check_budget(runner::cpu::core_budget()?)?;
+    campaign::record_run(&run)?;
+    println!("Running {} in {}", run, campaign::run_dir(&run));
+
+    drain(queue, fuzzers)
+}
+
+/// Continue the queued and interrupted campaigns of `run` after a restart.
+pub fn resume(run: &str, fuzzers: &FuzzersConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let run = campaign::resolve(run)?;
+    let queue = Queue::load(&run)?;
+    campaign::record_run(&run)?;
+    println!("Resuming {} with {}", run, queue.progress());
+
+    drain(queue, fuzzers)
+}
+
+fn drain(queue: Queue, fuzzers: &FuzzersConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let run = queue.run.clone();
+    let supervision = Supervision::load(&queue.sinks)?;
+    let monitors = launch_monitors();
+    if let Some(addr) = &queue.metrics {
+        let bound = metrics::serve(addr, monitors.clone())?;
+        println!("Serving metrics on http://{}/metrics", bound);
+    }
+    let fuzzers = fuzzers.clone();
+
+    let res = queue::schedule(queue, move |campaign| {
+        run_campaign(campaign, &fuzzers, &supervision, &monitors)
     });
+    campaign::finish_run(&run)?;
+    println!("Finished {} with {}", run, res?);
+
+    Ok(())
+}
+
+/// Print the progress of every campaign of `run`, or of the running runs,
+/// redrawn at the `watch` interval if given.
+pub fn status(
+    run: Option<&str>,
+    fuzzers: &FuzzersConfig,
+    watch: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    dashboard::status(run, fuzzers, watch)
+}
+
+/// Browse the runs in a web dashboard on `addr` until interrupted.
+pub fn serve(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let bound = web::serve(addr)?;
+    println!("Serving the dashboard on http://{}/", bound);
+    loop {
+        std::thread::park();
+    }
+}
+
+/// `run` names a run, `latest`, or an experiment standing for its newest run.
+pub fn stop(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    campaign::stop(&campaign::resolve(run)?)
+}
+
+pub fn report(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    report::report(&campaign::resolve(run)?)
+}
 
-    vec![pg_handle, my_handle, ma_handle]
+pub fn triage(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    report::triage(&campaign::resolve(run)?)
+}
+
+/// Remove `run`, or the runs outside `retention`. Runs still running are kept.
+pub fn clean(
+    run: Option<&str>,
+    retention: &campaign::Retention,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let expired = match run {
+        Some(run) => vec![campaign::resolve(run)?],
+        None => {
+            let now = chrono::Local::now().naive_local();
+            retention.expired(&campaign::runs(), now)
+        }
+    };
+
+    for run in expired {
+        if let Some(pid) = campaign::running_pid(&run) {
+            println!("Keeping {}, still running as pid {}", run, pid);
+            continue;
+        }
+        campaign::clean(&run)?;
+        println!("Removed {}", run);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    #[test]
+    fn test_copy_squirrel_configs() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = utils::test_dir("squirrel_configs");
+        let root = dir.join("root");
+        let config_dir = dir.join("tmp/config");
+        fs::create_dir_all(root.join("config/squirrel"))?;
+        fs::create_dir_all(&config_dir)?;
+        fs::write(
+            root.join("config/squirrel/config_sqlite.yml"),
+            "db: sqlite\n",
+        )?;
+
+        copy_squirrel_configs(
+            &root.display().to_string(),
+            &config_dir.display().to_string(),
+            &dir.join("copy.log").display().to_string(),
+        )?;
+        assert_eq!(
+            fs::read_to_string(config_dir.join("squirrel/config_sqlite.yml"))?,
+            "db: sqlite\n"
+        );
+        // The configs the runners read are shipped in the repository.
+        let shipped = Path::new(env!("CARGO_MANIFEST_DIR")).join("config/squirrel");
+        for db in ["postgresql", "mysql", "mariadb", "sqlite"] {
+            assert!(shipped.join(format!("config_{}.yml", db)).exists());
+        }
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,258 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    builder::Stamp,
+    campaign::Campaign,
+    constants::{CFLAGS, SQLSMITH_VERSION, SQUIRREL_VERSION},
+    source::{self, Source},
+};
+
+/// Everything needed to reproduce or audit a campaign, written to its `manifest.json`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub campaign: Campaign,
+    /// Version of fuzzer_compare that ran the campaign
+    pub tool_version: String,
+    pub fuzzer: Component,
+    pub target: Component,
+    /// Inputs of every recorded build step, keyed by `<stamp target>/<step>`
+    pub build: BTreeMap<String, Vec<String>>,
+    pub cflags: String,
+    /// Environment the fuzzer was launched with
+    pub env: BTreeMap<String, String>,
+    /// Command line the fuzzer was launched with
+    pub command: Vec<String>,
+    pub seeds: Option<Seeds>,
+    pub host: Host,
+    pub start_time: String,
+    pub end_time: Option<String>,
+}
+
+/// Fuzzer or target as it was built
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Component {
+    pub name: String,
+    pub version: String,
+    /// Pinned commit id or tarball SHA-256, absent for sources built in place
+    pub digest: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Seeds {
+    pub dir: String,
+    /// SHA-256 over the relative path and content of every seed
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Host {
+    pub hostname: String,
+    pub cpu_model: String,
+    pub kernel: String,
+    pub cores: usize,
+}
+
+impl Manifest {
+    /// Manifest of `campaign` launched now as `command`, from `seeds_dir` if it has seeds.
+    pub fn new<K: AsRef<str>, S: AsRef<str>>(
+        campaign: &Campaign,
+        fuzzer_source: Option<Source>,
+        envs: &HashMap<K, String>,
+        command: &[S],
+        seeds_dir: Option<&str>,
+    ) -> Self {
+        let fuzzer = match fuzzer_source {
+            Some(source) => Component::of(&source),
+            None => Component {
+                name: campaign.fuzzer.clone(),
+                version: String::new(),
+                digest: None,
+            },
+        };
+        let target = match source::get(&campaign.target, &campaign.version) {
+            Ok(source) => Component::of(&source),
+            Err(_) => Component {
+                name: campaign.target.clone(),
+                version: campaign.version.clone(),
+                digest: None,
+            },
+        };
+
+        // AFL++ is built as part of Squirrel.
+        let fuzzer_stamps = match campaign.fuzzer.as_str() {
+            "aflpp" => "squirrel",
+            name => name,
+        };
+        let mut build = BTreeMap::new();
+        for stamp_target in [
+            fuzzer_stamps.to_string(),
+            format!("{}-{}", campaign.target, campaign.version),
+        ] {
+            for (step, inputs) in Stamp::recorded(&stamp_target) {
+                build.insert(format!("{}/{}", stamp_target, step), inputs);
+            }
+        }
+
+        Self {
+            campaign: campaign.clone(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            fuzzer,
+            target,
+            build,
+            cflags: CFLAGS.to_string(),
+            env: envs
+                .iter()
+                .map(|(k, v)| (k.as_ref().to_string(), v.clone()))
+                .collect(),
+            command: command.iter().map(|s| s.as_ref().to_string()).collect(),
+            seeds: seeds_dir.and_then(|dir| {
+                Some(Seeds {
+                    dir: dir.to_string(),
+                    sha256: hash_dir(dir)?,
+                })
+            }),
+            host: Host::current(),
+            start_time: chrono::Local::now().to_rfc3339(),
+            end_time: None,
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path(&self.campaign), serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Save the manifest unless the campaign has one, so a restarted fuzzer keeps the original.
+    pub fn create(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if Path::new(&path(&self.campaign)).exists() {
+            return Ok(());
+        }
+
+        self.save()
+    }
+
+    pub fn load(campaign: &Campaign) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path(campaign))?)?)
+    }
+}
+
+/// Record the end time of `campaign`, if it got as far as launching its fuzzer.
+pub fn finish(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+    if !Path::new(&path(campaign)).exists() {
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load(campaign)?;
+    manifest.end_time = Some(chrono::Local::now().to_rfc3339());
+    manifest.save()
+}
+
+fn path(campaign: &Campaign) -> String {
+    format!("{}/manifest.json", campaign.dir())
+}
+
+/// Source of the built-in fuzzer `name`
+pub fn builtin_source(name: &str) -> Option<Source> {
+    match name {
+        "squirrel" | "aflpp" => source::get("squirrel", SQUIRREL_VERSION).ok(),
+        "sqlsmith" => source::get("sqlsmith", SQLSMITH_VERSION).ok(),
+        _ => None,
+    }
+}
+
+impl Component {
+    fn of(source: &Source) -> Self {
+        Self {
+            name: source.name.clone(),
+            version: source.version.clone(),
+            digest: source.pinned(),
+        }
+    }
+}
+
+impl Host {
+    fn current() -> Self {
+        let read = |path: &str| {
+            fs::read_to_string(path)
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        let cpu_model = read("/proc/cpuinfo")
+            .lines()
+            .find(|l| l.starts_with("model name"))
+            .and_then(|l| l.split_once(':'))
+            .map(|(_, model)| model.trim().to_string())
+            .unwrap_or_default();
+
+        Self {
+            hostname: read("/proc/sys/kernel/hostname"),
+            cpu_model,
+            kernel: read("/proc/sys/kernel/osrelease"),
+            cores: std::thread::available_parallelism().map_or(0, |n| n.get()),
+        }
+    }
+}
+
+/// SHA-256 over the relative path and content of every file below `dir`
+fn hash_dir(dir: &str) -> Option<String> {
+    let mut files = vec![];
+    let mut pending = vec![Path::new(dir).to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).ok()?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.strip_prefix(dir).ok()?.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(fs::read(&file).ok()?);
+        hasher.update([0]);
+    }
+
+    Some(
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_dir() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = crate::utils::test_dir("seeds");
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("a.sql"), "SELECT 1;")?;
+        fs::write(dir.join("sub/b.sql"), "SELECT 2;")?;
+        let dir_str = dir.display().to_string();
+
+        let before = hash_dir(&dir_str).unwrap();
+        assert_eq!(hash_dir(&dir_str).unwrap(), before);
+        fs::write(dir.join("sub/b.sql"), "SELECT 3;")?;
+        assert_ne!(hash_dir(&dir_str).unwrap(), before);
+        assert!(hash_dir("/nonexistent").is_none());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
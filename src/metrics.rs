@@ -0,0 +1,117 @@
+use std::net::SocketAddr;
+
+use crate::{
+    http::{self, Response},
+    Monitors,
+};
+
+/// Gauges exported per campaign: metric name, monitor column and help text
+const GAUGES: [(&str, &str, &str); 8] = [
+    (
+        "fuzzer_compare_edges_covered",
+        "edge_covered",
+        "Edges covered in the coverage map of the fuzzer",
+    ),
+    (
+        "fuzzer_compare_execs_per_second",
+        "execs_per_sec",
+        "Executions per second",
+    ),
+    ("fuzzer_compare_crashes", "crash_num", "Crashes found"),
+    ("fuzzer_compare_hangs", "hang_num", "Hangs found"),
+    (
+        "fuzzer_compare_region_coverage_percent",
+        "region_cov_percent",
+        "LLVM region coverage of the target",
+    ),
+    (
+        "fuzzer_compare_function_coverage_percent",
+        "func_cov_percent",
+        "LLVM function coverage of the target",
+    ),
+    (
+        "fuzzer_compare_line_coverage_percent",
+        "line_cov_percent",
+        "LLVM line coverage of the target",
+    ),
+    (
+        "fuzzer_compare_branch_coverage_percent",
+        "branch_cov_percent",
+        "LLVM branch coverage of the target",
+    ),
+];
+
+/// Labels and last metrics of one campaign
+type Sample = (Vec<(String, String)>, Vec<(String, f64)>);
+
+/// Export the last sample of every monitored campaign on `http://<addr>/metrics`.
+pub fn serve(addr: &str, monitors: Monitors) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    http::serve(addr, move |target| match target {
+        "/metrics" => {
+            let samples = monitors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|m| (m.labels().to_vec(), m.last_sample().to_vec()))
+                .collect::<Vec<_>>();
+            Response::ok("text/plain; version=0.0.4; charset=utf-8", render(&samples))
+        }
+        _ => Response::not_found(),
+    })
+}
+
+/// Samples in the Prometheus text format, leaving out the metrics a campaign does not report
+fn render(samples: &[Sample]) -> String {
+    let mut out = String::new();
+    for (name, column, help) in GAUGES {
+        out += &format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name);
+        for (labels, metrics) in samples {
+            let Some((_, value)) = metrics.iter().find(|(m, v)| m == column && *v >= 0.0) else {
+                continue;
+            };
+            let labels = labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+                .collect::<Vec<_>>();
+            out += &format!("{}{{{}}} {}\n", name, labels.join(","), value);
+        }
+    }
+
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let labels = vec![
+            ("fuzzer".to_string(), "squirrel".to_string()),
+            ("target".to_string(), "postgresql".to_string()),
+            ("trial".to_string(), "1".to_string()),
+        ];
+        let metrics = vec![
+            ("edge_covered".to_string(), 800.0),
+            ("execs_per_sec".to_string(), 1234.5),
+            ("line_cov_percent".to_string(), -1.0),
+        ];
+
+        let out = render(&[(labels, metrics)]);
+        assert!(out.contains("# TYPE fuzzer_compare_edges_covered gauge\n"));
+        assert!(out.contains(
+            "fuzzer_compare_edges_covered{fuzzer=\"squirrel\",target=\"postgresql\",trial=\"1\"} 800\n"
+        ));
+        assert!(out.contains("fuzzer_compare_execs_per_second{") && out.contains("} 1234.5\n"));
+        // Unknown coverage is left out rather than reported as -1.
+        assert!(!out.contains("fuzzer_compare_line_coverage_percent{"));
+        assert_eq!(escape("a\"b\\"), "a\\\"b\\\\");
+    }
+}
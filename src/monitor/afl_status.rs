@@ -1,15 +1,42 @@
-use crate::utils::ShellCommand;
+use std::fs;
+
+use super::{Collector, StatusMonitor};
 
 pub struct AflStatusMonitor {
     pub status_file: String,
 }
 
 pub struct AflStautsResult {
-    pub case_num: i32,
-    pub cycle_num: i32,
-    pub crash_num: i32,
-    pub edge_covered: i32,
+    pub case_num: i64,
+    pub cycle_num: i64,
+    pub crash_num: i64,
+    pub edge_covered: i64,
     pub edge_percent: f64,
+    pub execs_per_sec: f64,
+    pub hang_num: i64,
+    /// Unix time of the last new corpus entry, 0 before the first one
+    pub last_find: i64,
+    /// Unix time the fuzzer last wrote its stats
+    pub last_update: i64,
+    pub fuzzer_pid: i32,
+}
+
+impl AflStautsResult {
+    /// Result with every stat unknown
+    pub fn unknown() -> Self {
+        Self {
+            case_num: -1,
+            cycle_num: -1,
+            crash_num: -1,
+            edge_covered: -1,
+            edge_percent: -1.0,
+            execs_per_sec: -1.0,
+            hang_num: -1,
+            last_find: -1,
+            last_update: -1,
+            fuzzer_pid: -1,
+        }
+    }
 }
 
 impl AflStatusMonitor {
@@ -19,25 +46,35 @@ impl AflStatusMonitor {
 
     pub fn get_result(&self) -> AflStautsResult {
         let case_num = self
-            .grep_item("corpus_count")
-            .map(|v| v.parse().unwrap_or(-1))
-            .unwrap();
+            .read_item("corpus_count")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
         let cycle_num = self
-            .grep_item("execs_done")
-            .map(|v| v.parse().unwrap_or(-1))
-            .unwrap();
+            .read_item("execs_done")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
         let crash_num = self
-            .grep_item("saved_crashes")
-            .map(|v| v.parse().unwrap_or(-1))
-            .unwrap();
+            .read_item("saved_crashes")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
         let edge_covered = self
-            .grep_item("edges_found")
-            .map(|v| v.parse().unwrap_or(-1))
-            .unwrap();
+            .read_item("edges_found")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
         let edge_percent = self
-            .grep_item("bitmap_cvg")
-            .map(|v| v.strip_suffix('%').unwrap().parse().unwrap_or(-1.0))
-            .unwrap();
+            .read_item("bitmap_cvg")
+            .map_or(-1.0, |v| v.trim_end_matches('%').parse().unwrap_or(-1.0));
+        let execs_per_sec = self
+            .read_item("execs_per_sec")
+            .map_or(-1.0, |v| v.parse().unwrap_or(-1.0));
+        let hang_num = self
+            .read_item("saved_hangs")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
+        let last_find = self
+            .read_item("last_find")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
+        let last_update = self
+            .read_item("last_update")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
+        let fuzzer_pid = self
+            .read_item("fuzzer_pid")
+            .map_or(-1, |v| v.parse().unwrap_or(-1));
 
         AflStautsResult {
             case_num,
@@ -45,20 +82,74 @@ impl AflStatusMonitor {
             crash_num,
             edge_covered,
             edge_percent,
+            execs_per_sec,
+            hang_num,
+            last_find,
+            last_update,
+            fuzzer_pid,
         }
     }
 
-    fn grep_item(&self, pattern: &str) -> Option<String> {
-        let grep_cmd = ["grep", pattern, &self.status_file];
+    fn read_item(&self, key: &str) -> Option<String> {
+        let content = fs::read_to_string(&self.status_file).ok()?;
+        stat_value(&content, key)
+    }
+}
+
+impl Collector for StatusMonitor {
+    fn columns(&self) -> Vec<String> {
+        [
+            "case_num",
+            "cycle_num",
+            "crash_num",
+            "error_num",
+            "edge_covered",
+            "edge_percent",
+            "execs_per_sec",
+            "hang_num",
+            "last_find",
+        ]
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        let (status, error_num) = self.get_result();
+        vec![
+            status.case_num as f64,
+            status.cycle_num as f64,
+            status.crash_num as f64,
+            error_num as f64,
+            status.edge_covered as f64,
+            status.edge_percent,
+            status.execs_per_sec,
+            status.hang_num as f64,
+            status.last_find as f64,
+        ]
+    }
+}
+
+/// Value of `key` in AFL stats, whose lines look like `corpus_count      : 12`
+fn stat_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
 
-        let output = ShellCommand::new()
-            .args(&grep_cmd)
-            .pipe_stdio()
-            .spawn()
-            .ok()?
-            .wait_with_output()
-            .ok()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        String::from_utf8(output.stdout).ok()
+    #[test]
+    fn test_stat_value() {
+        let content = "execs_done        : 4294967296
+                       execs_per_sec     : 1024.50
+                       execs_ps_last_min : 980.00
+";
+        assert_eq!(stat_value(content, "execs_done").unwrap(), "4294967296");
+        assert_eq!(stat_value(content, "execs_per_sec").unwrap(), "1024.50");
+        assert!(stat_value(content, "execs").is_none());
     }
 }
@@ -0,0 +1,99 @@
+use std::{cell::Cell, fs};
+
+use super::Collector;
+
+/// Samples the resources a campaign cgroup consumed so far
+pub struct CgroupMonitor {
+    pub cgroup_dir: String,
+    /// Highest `memory.current` seen, for kernels without `memory.peak`
+    max_memory: Cell<i64>,
+}
+
+pub struct CgroupResult {
+    pub cpu_secs: f64,
+    pub mem_peak: i64,
+    pub oom_kills: i64,
+}
+
+impl CgroupMonitor {
+    pub fn new(cgroup_dir: String) -> Self {
+        Self {
+            cgroup_dir,
+            max_memory: Cell::new(-1),
+        }
+    }
+
+    pub fn get_result(&self) -> CgroupResult {
+        let cpu_secs = self
+            .read_key("cpu.stat", "usage_usec")
+            .map_or(-1.0, |usec| usec as f64 / 1e6);
+
+        let current = self.read_value("memory.current").unwrap_or(-1);
+        self.max_memory.set(self.max_memory.get().max(current));
+        let mem_peak = self
+            .read_value("memory.peak")
+            .unwrap_or(self.max_memory.get());
+
+        let oom_kills = self.read_key("memory.events", "oom_kill").unwrap_or(-1);
+
+        CgroupResult {
+            cpu_secs,
+            mem_peak,
+            oom_kills,
+        }
+    }
+
+    fn read_value(&self, file: &str) -> Option<i64> {
+        fs::read_to_string(format!("{}/{}", self.cgroup_dir, file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// Value of `key` in a flat keyed file such as `cpu.stat`
+    fn read_key(&self, file: &str, key: &str) -> Option<i64> {
+        parse_key(
+            &fs::read_to_string(format!("{}/{}", self.cgroup_dir, file)).ok()?,
+            key,
+        )
+    }
+}
+
+impl Collector for CgroupMonitor {
+    fn columns(&self) -> Vec<String> {
+        ["cpu_secs", "mem_peak", "oom_kills"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        let res = self.get_result();
+        vec![res.cpu_secs, res.mem_peak as f64, res.oom_kills as f64]
+    }
+}
+
+fn parse_key(content: &str, key: &str) -> Option<i64> {
+    content
+        .lines()
+        .filter_map(|l| l.split_once(' '))
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, v)| v.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        let events = "low 0\nhigh 0\nmax 12\noom 2\noom_kill 1\n";
+        assert_eq!(parse_key(events, "oom_kill"), Some(1));
+        assert_eq!(parse_key(events, "oom"), Some(2));
+        assert_eq!(parse_key(events, "oom_group_kill"), None);
+
+        let monitor = CgroupMonitor::new("/nonexistent".to_string());
+        assert_eq!(monitor.get_result().oom_kills, -1);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use super::{
+    CgroupMonitor, CommandMonitor, CorpusMonitor, DiskMonitor, LLVMCoverageMonitor, Reference,
+    ReferenceConfig, StatusKind, StatusMonitor, ValidityMonitor,
+};
+
+/// Built-in collectors a campaign can be sampled with
+pub const BUILTIN_COLLECTORS: [&str; 6] = [
+    "stats",
+    "coverage",
+    "resources",
+    "disk",
+    "corpus",
+    "validity",
+];
+
+/// Source of named metrics sampled together
+pub trait Collector: Send {
+    /// Names of the metrics, the columns they are written to
+    fn columns(&self) -> Vec<String>;
+
+    /// Current value of every metric in the order of `columns`, -1 where unknown
+    fn collect(&self) -> Vec<f64>;
+}
+
+/// Collectors sampled for every campaign, read from `config/collectors.toml`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CollectorsConfig {
+    /// Built-in collectors, all of them by default
+    #[serde(default = "default_builtin")]
+    pub builtin: Vec<String>,
+
+    #[serde(default)]
+    pub validity: ValidityConfig,
+
+    #[serde(default)]
+    pub command: Vec<CommandConfig>,
+}
+
+/// How the validity of the queued statements is measured
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, default)]
+pub struct ValidityConfig {
+    /// Test cases replayed per sample
+    pub sample: usize,
+
+    /// Reference servers by target, SQLite is replayed in memory unless listed
+    pub reference: BTreeMap<String, ReferenceConfig>,
+}
+
+/// Shell command printing a JSON object with its metrics, run in the campaign directory
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CommandConfig {
+    pub command: String,
+
+    /// Keys of the printed object, the columns they are written to
+    pub metrics: Vec<String>,
+
+    /// Fuzzers to run the command for, all by default
+    #[serde(default)]
+    pub fuzzers: Vec<String>,
+
+    /// Targets to run the command for, all by default
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+/// What one collector of a campaign samples
+#[derive(Clone)]
+pub enum CollectorConfig {
+    /// Stats file of the fuzzer in the given format
+    Stats(StatusKind, String),
+    /// Coverage of the instrumented binary from the profiles in a directory
+    Coverage { exec_path: String, prof_dir: String },
+    /// CPU time, peak memory and OOM kills of a cgroup
+    Resources(String),
+    /// Size of a directory
+    Disk(String),
+    /// Size and statements of the test cases in a queue directory
+    Corpus(String),
+    /// Validity of the statements in a queue directory, logging error categories to a file
+    Validity {
+        queue_dir: String,
+        reference: Reference,
+        sample: usize,
+        log_file: String,
+    },
+    /// Metrics printed by a command run in a directory
+    Command { config: CommandConfig, dir: String },
+}
+
+fn default_builtin() -> Vec<String> {
+    BUILTIN_COLLECTORS.iter().map(|c| c.to_string()).collect()
+}
+
+impl Default for CollectorsConfig {
+    fn default() -> Self {
+        Self {
+            builtin: default_builtin(),
+            validity: ValidityConfig::default(),
+            command: vec![],
+        }
+    }
+}
+
+impl Default for ValidityConfig {
+    fn default() -> Self {
+        Self {
+            sample: 100,
+            reference: BTreeMap::new(),
+        }
+    }
+}
+
+impl CollectorsConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        let config: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        if let Some(name) = config
+            .builtin
+            .iter()
+            .find(|c| !BUILTIN_COLLECTORS.contains(&c.as_str()))
+        {
+            return Err(format!(
+                "Unknown collector {}, expected one of {:?}",
+                name, BUILTIN_COLLECTORS
+            )
+            .into());
+        }
+
+        Ok(config)
+    }
+
+    pub fn has(&self, builtin: &str) -> bool {
+        self.builtin.iter().any(|c| c == builtin)
+    }
+}
+
+impl ValidityConfig {
+    /// Database the statements of `target` are replayed against, if any
+    pub fn reference(&self, target: &str) -> Option<Reference> {
+        match self.reference.get(target) {
+            Some(config) => Some(Reference::Command(config.clone())),
+            None if target == "sqlite" => Some(Reference::Sqlite),
+            None => None,
+        }
+    }
+}
+
+impl CommandConfig {
+    /// Whether the command runs for a campaign of `fuzzer` against `target`
+    pub fn applies(&self, fuzzer: &str, target: &str) -> bool {
+        (self.fuzzers.is_empty() || self.fuzzers.iter().any(|f| f == fuzzer))
+            && (self.targets.is_empty() || self.targets.iter().any(|t| t == target))
+    }
+}
+
+impl CollectorConfig {
+    pub fn open(&self) -> Box<dyn Collector> {
+        match self {
+            Self::Stats(kind, file) => Box::new(StatusMonitor::new(*kind, file.clone())),
+            Self::Coverage {
+                exec_path,
+                prof_dir,
+            } => Box::new(LLVMCoverageMonitor::new(
+                exec_path.clone(),
+                prof_dir.clone(),
+            )),
+            Self::Resources(cgroup_dir) => Box::new(CgroupMonitor::new(cgroup_dir.clone())),
+            Self::Disk(dir) => Box::new(DiskMonitor::new(dir.clone())),
+            Self::Corpus(queue_dir) => Box::new(CorpusMonitor::new(queue_dir.clone())),
+            Self::Validity {
+                queue_dir,
+                reference,
+                sample,
+                log_file,
+            } => Box::new(ValidityMonitor::new(
+                queue_dir.clone(),
+                reference.clone(),
+                *sample,
+                log_file.clone(),
+            )),
+            Self::Command { config, dir } => Box::new(CommandMonitor::new(
+                config.command.clone(),
+                config.metrics.clone(),
+                dir.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collectors_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config: CollectorsConfig = toml::from_str(
+            "builtin = [\"stats\", \"disk\"]\n\
+             [validity.reference.postgresql]\n\
+             command = \"psql -X -q -v ON_ERROR_STOP=1 -p 5499 reference\"\n\
+             [[command]]\n\
+             command = \"echo '{\\\"locks\\\": 3}'\"\n\
+             metrics = [\"locks\"]\n\
+             targets = [\"postgresql\"]\n",
+        )?;
+        assert!(config.has("disk"));
+        assert!(!config.has("coverage"));
+        assert!(config.command[0].applies("squirrel", "postgresql"));
+        assert!(!config.command[0].applies("squirrel", "mysql"));
+        assert!(config.validity.reference("postgresql").is_some());
+        assert!(config.validity.reference("mysql").is_none());
+        assert!(matches!(
+            config.validity.reference("sqlite"),
+            Some(Reference::Sqlite)
+        ));
+
+        let defaults: CollectorsConfig = toml::from_str("")?;
+        assert_eq!(defaults.builtin.len(), BUILTIN_COLLECTORS.len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::process::Command;
+
+use super::Collector;
+
+/// Runs a shell command printing a JSON object of metrics
+pub struct CommandMonitor {
+    pub command: String,
+    pub metrics: Vec<String>,
+    /// Directory the command runs in
+    pub dir: String,
+}
+
+impl CommandMonitor {
+    pub fn new(command: String, metrics: Vec<String>, dir: String) -> Self {
+        Self {
+            command,
+            metrics,
+            dir,
+        }
+    }
+
+    /// Value of every metric, -1 where the command failed or left it out
+    pub fn get_result(&self) -> Vec<f64> {
+        let output = Command::new("/bin/sh")
+            .args(["-c", &self.command])
+            .current_dir(&self.dir)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+
+        self.parse(&output)
+    }
+
+    fn parse(&self, output: &str) -> Vec<f64> {
+        let values: serde_json::Value = serde_json::from_str(output).unwrap_or_default();
+        self.metrics
+            .iter()
+            .map(|m| values[m].as_f64().unwrap_or(-1.0))
+            .collect()
+    }
+}
+
+impl Collector for CommandMonitor {
+    fn columns(&self) -> Vec<String> {
+        self.metrics.clone()
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        self.get_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_metrics() {
+        let monitor = CommandMonitor::new(
+            "echo '{\"locks\": 3, \"sessions\": 1.5}'".to_string(),
+            vec![
+                "locks".to_string(),
+                "sessions".to_string(),
+                "missing".to_string(),
+            ],
+            "/".to_string(),
+        );
+        assert_eq!(monitor.get_result(), vec![3.0, 1.5, -1.0]);
+
+        let failing = CommandMonitor::new(
+            "exit 1".to_string(),
+            vec!["locks".to_string()],
+            "/".to_string(),
+        );
+        assert_eq!(failing.get_result(), vec![-1.0]);
+    }
+}
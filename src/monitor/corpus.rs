@@ -0,0 +1,200 @@
+use std::{collections::BTreeSet, fs};
+
+use super::{
+    sql::{self, STATEMENT_TYPES},
+    Collector,
+};
+
+/// Analyses the test cases a fuzzer keeps in its queue
+pub struct CorpusMonitor {
+    pub queue_dir: String,
+}
+
+/// Shape of a queue, -1 where unknown
+pub struct CorpusResult {
+    pub files: i64,
+    pub bytes: i64,
+    /// Smallest, median, 90th percentile and largest test case in bytes
+    pub size_min: i64,
+    pub size_median: i64,
+    pub size_p90: i64,
+    pub size_max: i64,
+    pub statements_per_case: f64,
+    /// Statements of each of `STATEMENT_TYPES`, followed by all others
+    pub statement_types: [i64; STATEMENT_TYPES.len() + 1],
+    pub avg_depth: f64,
+    pub max_depth: i64,
+    /// Distinct SQL keywords used anywhere in the queue
+    pub keywords: i64,
+}
+
+impl CorpusResult {
+    pub fn unknown() -> Self {
+        Self {
+            files: -1,
+            bytes: -1,
+            size_min: -1,
+            size_median: -1,
+            size_p90: -1,
+            size_max: -1,
+            statements_per_case: -1.0,
+            statement_types: [-1; STATEMENT_TYPES.len() + 1],
+            avg_depth: -1.0,
+            max_depth: -1,
+            keywords: -1,
+        }
+    }
+}
+
+impl CorpusMonitor {
+    pub fn new(queue_dir: String) -> Self {
+        Self { queue_dir }
+    }
+
+    /// Statistics of the test cases in the queue, unknown before the fuzzer created it
+    pub fn get_result(&self) -> CorpusResult {
+        let Ok(entries) = fs::read_dir(&self.queue_dir) else {
+            return CorpusResult::unknown();
+        };
+
+        let mut sizes = vec![];
+        let mut statements = 0;
+        let mut statement_types = [0; STATEMENT_TYPES.len() + 1];
+        let mut total_depth = 0;
+        let mut max_depth = 0;
+        let mut keywords = BTreeSet::new();
+        for entry in entries.flatten() {
+            // AFL keeps its bookkeeping in dot directories next to the test cases.
+            if !entry.file_type().is_ok_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(content) = fs::read(entry.path()) else {
+                continue;
+            };
+            sizes.push(content.len() as i64);
+
+            for statement in sql::split_statements(&String::from_utf8_lossy(&content)) {
+                let stats = sql::analyze(&statement);
+                let kind = STATEMENT_TYPES.iter().position(|t| *t == stats.kind);
+                statement_types[kind.unwrap_or(STATEMENT_TYPES.len())] += 1;
+                statements += 1;
+                total_depth += stats.depth;
+                max_depth = max_depth.max(stats.depth);
+                keywords.extend(stats.keywords);
+            }
+        }
+        sizes.sort();
+
+        let files = sizes.len();
+        CorpusResult {
+            files: files as i64,
+            bytes: sizes.iter().sum(),
+            size_min: percentile(&sizes, 0),
+            size_median: percentile(&sizes, 50),
+            size_p90: percentile(&sizes, 90),
+            size_max: percentile(&sizes, 100),
+            statements_per_case: ratio(statements, files),
+            statement_types,
+            avg_depth: ratio(total_depth, statements),
+            max_depth: if statements == 0 {
+                -1
+            } else {
+                max_depth as i64
+            },
+            keywords: keywords.len() as i64,
+        }
+    }
+}
+
+impl Collector for CorpusMonitor {
+    fn columns(&self) -> Vec<String> {
+        let mut columns = [
+            "corpus_files",
+            "corpus_bytes",
+            "corpus_size_min",
+            "corpus_size_median",
+            "corpus_size_p90",
+            "corpus_size_max",
+            "corpus_stmts_per_case",
+        ]
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>();
+        columns.extend(STATEMENT_TYPES.iter().map(|t| format!("stmt_{}", t)));
+        columns.push("stmt_other".to_string());
+        columns.extend(
+            ["corpus_avg_depth", "corpus_max_depth", "corpus_keywords"]
+                .iter()
+                .map(|c| c.to_string()),
+        );
+        columns
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        let res = self.get_result();
+        let mut values = vec![
+            res.files as f64,
+            res.bytes as f64,
+            res.size_min as f64,
+            res.size_median as f64,
+            res.size_p90 as f64,
+            res.size_max as f64,
+            res.statements_per_case,
+        ];
+        values.extend(res.statement_types.iter().map(|n| *n as f64));
+        values.extend([res.avg_depth, res.max_depth as f64, res.keywords as f64]);
+        values
+    }
+}
+
+/// Nearest-rank percentile `p` of sorted values, -1 if there are none
+fn percentile(sorted: &[i64], p: usize) -> i64 {
+    if sorted.is_empty() {
+        return -1;
+    }
+
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        return -1.0;
+    }
+
+    part as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_stats() -> Result<(), Box<dyn std::error::Error>> {
+        let queue_dir = crate::utils::test_dir("corpus");
+        fs::create_dir_all(queue_dir.join(".state"))?;
+        fs::write(
+            queue_dir.join("id:000000"),
+            "CREATE TABLE t (a INT); SELECT a FROM t WHERE a IN (SELECT 1);",
+        )?;
+        fs::write(queue_dir.join("id:000001"), "VACUUM;")?;
+
+        let monitor = CorpusMonitor::new(queue_dir.display().to_string());
+        let res = monitor.get_result();
+        assert_eq!(res.files, 2);
+        assert_eq!(res.size_min, 7);
+        assert_eq!(res.size_max, 62);
+        assert_eq!(res.statements_per_case, 1.5);
+        assert_eq!(res.statement_types, [1, 0, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(res.max_depth, 1);
+        assert_eq!(res.keywords, 7);
+        assert_eq!(monitor.columns().len(), monitor.collect().len());
+
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 90), 9);
+        let missing = CorpusMonitor::new("/nonexistent".to_string());
+        assert_eq!(missing.collect()[0], -1.0);
+
+        fs::remove_dir_all(&queue_dir)?;
+        Ok(())
+    }
+}
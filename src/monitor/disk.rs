@@ -0,0 +1,49 @@
+use std::{fs, path::Path};
+
+use super::Collector;
+
+/// Measures the space a campaign directory takes up
+pub struct DiskMonitor {
+    pub dir: String,
+}
+
+impl DiskMonitor {
+    pub fn new(dir: String) -> Self {
+        Self { dir }
+    }
+
+    /// Bytes of all files below the directory, -1 if it does not exist
+    pub fn get_result(&self) -> i64 {
+        if !Path::new(&self.dir).exists() {
+            return -1;
+        }
+
+        let mut bytes = 0;
+        let mut pending = vec![Path::new(&self.dir).to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                // Servers remove files while they are being walked.
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if meta.is_dir() {
+                    pending.push(entry.path());
+                } else {
+                    bytes += meta.len() as i64;
+                }
+            }
+        }
+
+        bytes
+    }
+}
+
+impl Collector for DiskMonitor {
+    fn columns(&self) -> Vec<String> {
+        vec!["disk_bytes".to_string()]
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        vec![self.get_result() as f64]
+    }
+}
@@ -0,0 +1,194 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// State of a campaign as judged by the watchdog, worst last
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    #[default]
+    Healthy,
+    /// Running but slow, or its server refuses connections
+    Degraded,
+    /// Running without progress
+    Stalled,
+    /// Fuzzer gone
+    Dead,
+}
+
+/// Health rules of the watchdog, read from `config/watchdog.toml`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, default)]
+pub struct WatchdogConfig {
+    /// Seconds without fresh stats or new executions before a campaign counts as stalled
+    pub stall_secs: i64,
+
+    /// Seconds without fresh stats before a campaign counts as dead
+    pub dead_secs: i64,
+
+    /// Executions per second below which a campaign counts as degraded
+    pub min_execs_per_sec: f64,
+
+    /// Restart stalled and dead fuzzers through their runner
+    pub restart: bool,
+
+    /// Restarts of one campaign before the watchdog gives up on it
+    pub max_restarts: u32,
+}
+
+/// Signs of life of a campaign in one sample, -1 or absent where unknown
+pub struct Vitals {
+    pub execs: i64,
+    pub execs_per_sec: f64,
+    /// Unix time the fuzzer last wrote its stats
+    pub updated_at: i64,
+    pub fuzzer_alive: Option<bool>,
+    pub server_up: Option<bool>,
+}
+
+/// Judges the health of one campaign from its successive samples
+pub struct Watchdog {
+    /// Unix time the fuzzer was launched, the grace period before its first stats
+    launched_at: i64,
+    execs: i64,
+    /// Unix time the executions last advanced
+    progressed_at: i64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            stall_secs: 600,
+            dead_secs: 1800,
+            min_execs_per_sec: 1.0,
+            restart: false,
+            max_restarts: 3,
+        }
+    }
+}
+
+impl WatchdogConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+impl std::fmt::Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Stalled => "stalled",
+            Self::Dead => "dead",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Watchdog {
+    /// Watchdog of a fuzzer launched at unix time `now`
+    pub fn new(now: i64) -> Self {
+        Self {
+            launched_at: now,
+            execs: -1,
+            progressed_at: now,
+        }
+    }
+
+    /// Health according to `config` given the `vitals` sampled at unix time `now`.
+    pub fn check(&mut self, config: &WatchdogConfig, vitals: &Vitals, now: i64) -> Health {
+        // Stats not newer than the launch are left over from the fuzzer before a restart,
+        // its pid and executions say nothing about the one running now.
+        let current = vitals.updated_at > self.launched_at;
+        if current && vitals.execs > self.execs {
+            self.execs = vitals.execs;
+            self.progressed_at = now;
+        }
+        // Before its first stats a fuzzer is judged from its launch.
+        let stale_secs = now - vitals.updated_at.max(self.launched_at);
+        let idle_secs = now - self.progressed_at;
+
+        if (current && vitals.fuzzer_alive == Some(false)) || stale_secs >= config.dead_secs {
+            Health::Dead
+        } else if stale_secs >= config.stall_secs || idle_secs >= config.stall_secs {
+            Health::Stalled
+        } else if vitals.server_up == Some(false)
+            || (0.0..config.min_execs_per_sec).contains(&vitals.execs_per_sec)
+        {
+            Health::Degraded
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog() {
+        let config = WatchdogConfig {
+            stall_secs: 300,
+            dead_secs: 900,
+            ..WatchdogConfig::default()
+        };
+        let vitals = |execs, execs_per_sec, updated_at| Vitals {
+            execs,
+            execs_per_sec,
+            updated_at,
+            fuzzer_alive: Some(true),
+            server_up: Some(true),
+        };
+        let mut watchdog = Watchdog::new(0);
+
+        // No stats yet, but still within the grace period.
+        assert_eq!(
+            watchdog.check(&config, &vitals(-1, -1.0, -1), 60),
+            Health::Healthy
+        );
+        assert_eq!(
+            watchdog.check(&config, &vitals(1000, 500.0, 100), 120),
+            Health::Healthy
+        );
+        assert_eq!(
+            watchdog.check(&config, &vitals(1100, 0.5, 150), 180),
+            Health::Degraded
+        );
+        // Fresh stats, but no new executions.
+        assert_eq!(
+            watchdog.check(&config, &vitals(1100, 0.0, 470), 480),
+            Health::Stalled
+        );
+        assert_eq!(
+            watchdog.check(&config, &vitals(1100, 0.0, 470), 1400),
+            Health::Dead
+        );
+        let gone = Vitals {
+            fuzzer_alive: Some(false),
+            ..vitals(2000, 500.0, 1400)
+        };
+        assert_eq!(watchdog.check(&config, &gone, 1410), Health::Dead);
+
+        // Right after a restart, the stats still name the killed fuzzer.
+        let mut watchdog = Watchdog::new(1420);
+        assert_eq!(watchdog.check(&config, &gone, 1450), Health::Healthy);
+        // The restarted fuzzer starts counting its executions again.
+        assert_eq!(
+            watchdog.check(&config, &vitals(10, 500.0, 1500), 1510),
+            Health::Healthy
+        );
+        assert_eq!(
+            watchdog.check(&config, &vitals(900, 500.0, 1780), 1790),
+            Health::Healthy
+        );
+
+        let config: WatchdogConfig = toml::from_str("restart = true").unwrap();
+        assert!(config.restart);
+        assert_eq!(config.stall_secs, 600);
+    }
+}
@@ -0,0 +1,57 @@
+use std::fs;
+
+use super::AflStautsResult;
+
+/// Reads a JSON stats object whose keys are named after the CSV columns
+pub struct JsonStatsMonitor {
+    pub stats_file: String,
+}
+
+impl JsonStatsMonitor {
+    pub fn new(stats_file: String) -> Self {
+        Self { stats_file }
+    }
+
+    /// Stats in the AFL columns together with the error count, -1 where missing
+    pub fn get_result(&self) -> (AflStautsResult, i64) {
+        let content = fs::read_to_string(&self.stats_file).unwrap_or_default();
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> (AflStautsResult, i64) {
+        let stats: serde_json::Value = serde_json::from_str(content).unwrap_or_default();
+        let int = |key: &str| stats[key].as_i64().unwrap_or(-1);
+
+        let status = AflStautsResult {
+            case_num: int("case_num"),
+            cycle_num: int("cycle_num"),
+            crash_num: int("crash_num"),
+            edge_covered: int("edge_covered"),
+            edge_percent: stats["edge_percent"].as_f64().unwrap_or(-1.0),
+            execs_per_sec: stats["execs_per_sec"].as_f64().unwrap_or(-1.0),
+            hang_num: int("hang_num"),
+            last_find: int("last_find"),
+            last_update: int("last_update"),
+            fuzzer_pid: int("fuzzer_pid").try_into().unwrap_or(-1),
+        };
+
+        (status, int("error_num"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_stats() {
+        let (status, error_num) =
+            JsonStatsMonitor::parse(r#"{"case_num": 12, "edge_percent": 3.5, "error_num": 4}"#);
+
+        assert_eq!(status.case_num, 12);
+        assert_eq!(status.crash_num, -1);
+        assert_eq!(status.edge_percent, 3.5);
+        assert_eq!(error_num, 4);
+        assert_eq!(JsonStatsMonitor::parse("").0.case_num, -1);
+    }
+}
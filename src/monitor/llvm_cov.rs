@@ -1,5 +1,6 @@
 use std::fs;
 
+use super::Collector;
 use crate::utils::ShellCommand;
 
 pub struct LLVMCoverageMonitor {
@@ -22,6 +23,23 @@ pub struct LLVMCoverageItem {
     pub percent: f64,
 }
 
+impl LLVMCoverageResult {
+    fn unknown() -> Self {
+        let item = || LLVMCoverageItem {
+            missed: -1,
+            total: -1,
+            percent: -1.0,
+        };
+
+        Self {
+            region_cov: item(),
+            func_cov: item(),
+            line_cov: item(),
+            branch_cov: item(),
+        }
+    }
+}
+
 impl LLVMCoverageMonitor {
     pub fn new(exec_path: String, profile_dir: String) -> Self {
         Self {
@@ -30,94 +48,50 @@ impl LLVMCoverageMonitor {
         }
     }
 
+    /// Coverage of the profiles written so far, -1 everywhere if there are none yet
     pub fn get_result(&self) -> LLVMCoverageResult {
-        self.merge().expect("Failed to merge llvm profraws");
-        let raw_output = self.report().unwrap();
-
-        let mut unparsed_output = raw_output.lines().last().unwrap().split_whitespace();
-        unparsed_output.next();
-
-        let region_total = unparsed_output.next().unwrap().parse().unwrap();
-        let region_missed = unparsed_output.next().unwrap().parse().unwrap();
-        let region_percent = unparsed_output
-            .next()
-            .unwrap()
-            .strip_suffix('%')
-            .unwrap()
-            .parse()
-            .unwrap();
-
-        let region_cov = LLVMCoverageItem {
-            missed: region_missed,
-            total: region_total,
-            percent: region_percent,
-        };
-
-        let func_total = unparsed_output.next().unwrap().parse().unwrap();
-        let func_missed = unparsed_output.next().unwrap().parse().unwrap();
-        let func_percent = unparsed_output
-            .next()
-            .unwrap()
-            .strip_suffix('%')
-            .unwrap()
-            .parse()
-            .unwrap();
-
-        let func_cov = LLVMCoverageItem {
-            missed: func_missed,
-            total: func_total,
-            percent: func_percent,
-        };
-
-        let line_total = unparsed_output.next().unwrap().parse().unwrap();
-        let line_missed = unparsed_output.next().unwrap().parse().unwrap();
-        let line_percent = unparsed_output
-            .next()
-            .unwrap()
-            .strip_suffix('%')
-            .unwrap()
-            .parse()
-            .unwrap();
-
-        let line_cov = LLVMCoverageItem {
-            missed: line_missed,
-            total: line_total,
-            percent: line_percent,
-        };
+        self.merge()
+            .ok()
+            .and_then(|_| self.report())
+            .and_then(|output| Self::parse(&output))
+            .unwrap_or_else(LLVMCoverageResult::unknown)
+    }
 
-        let branch_total = unparsed_output.next().unwrap().parse().unwrap();
-        let branch_missed = unparsed_output.next().unwrap().parse().unwrap();
-        let branch_percent = unparsed_output
-            .next()
-            .unwrap()
-            .strip_suffix('%')
-            .unwrap()
-            .parse()
-            .unwrap();
-
-        let branch_cov = LLVMCoverageItem {
-            missed: branch_missed,
-            total: branch_total,
-            percent: branch_percent,
+    /// Parse the `TOTAL` line of `llvm-cov report`.
+    fn parse(output: &str) -> Option<LLVMCoverageResult> {
+        let mut fields = output.lines().last()?.split_whitespace();
+        fields.next();
+
+        let mut item = || -> Option<LLVMCoverageItem> {
+            let total = fields.next()?.parse().ok()?;
+            let missed = fields.next()?.parse().ok()?;
+            let percent = fields.next()?.strip_suffix('%')?.parse().ok()?;
+            Some(LLVMCoverageItem {
+                missed,
+                total,
+                percent,
+            })
         };
 
-        LLVMCoverageResult {
-            region_cov,
-            func_cov,
-            line_cov,
-            branch_cov,
-        }
+        Some(LLVMCoverageResult {
+            region_cov: item()?,
+            func_cov: item()?,
+            line_cov: item()?,
+            branch_cov: item()?,
+        })
     }
 
     fn merge(&self) -> Result<(), Box<dyn std::error::Error>> {
         let mut profraws = vec![];
-        let paths = fs::read_dir(&self.profile_dir).unwrap();
-        for f in paths {
-            let f = f.unwrap().path();
-            if f.ends_with("profraw") {
+        for f in fs::read_dir(&self.profile_dir)? {
+            let f = f?.path();
+            if f.extension().is_some_and(|e| e == "profraw") {
                 profraws.push(f.display().to_string());
             }
         }
+        if profraws.is_empty() {
+            return Err("No profiles written yet".into());
+        }
 
         let llvm_merge_cmd = [
             "llvm-profdata",
@@ -155,3 +129,45 @@ impl LLVMCoverageMonitor {
         String::from_utf8(output.stdout).ok()
     }
 }
+
+impl Collector for LLVMCoverageMonitor {
+    fn columns(&self) -> Vec<String> {
+        [
+            "region_cov_percent",
+            "func_cov_percent",
+            "line_cov_percent",
+            "branch_cov_percent",
+        ]
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        let res = self.get_result();
+        vec![
+            res.region_cov.percent,
+            res.func_cov.percent,
+            res.line_cov.percent,
+            res.branch_cov.percent,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report() {
+        let output = "Filename  Regions  Missed Regions  Cover\n\
+            TOTAL  200  150  25.00%  40  30  25.00%  1000  900  10.00%  80  60  25.00%\n";
+        let res = LLVMCoverageMonitor::parse(output).unwrap();
+
+        assert_eq!(res.region_cov.total, 200);
+        assert_eq!(res.line_cov.missed, 900);
+        assert_eq!(res.line_cov.percent, 10.0);
+        assert_eq!(res.branch_cov.percent, 25.0);
+        assert!(LLVMCoverageMonitor::parse("error: no profile\n").is_none());
+    }
+}
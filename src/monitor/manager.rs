@@ -1,109 +1,373 @@
-use std::fs;
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
 
+use serde::Deserialize;
+
+use crate::campaign;
 use crate::constants::DATETIME_FORMAT_STR;
+use crate::hooks::{Event, HooksConfig, Sample, Tracker};
 use crate::monitor::{AflStatusMonitor, AflStautsResult};
 
-use super::{LLVMCoverageMonitor, LLVMCoverageResult};
+use super::{
+    collector::{Collector, CollectorConfig},
+    health::{Health, Vitals, Watchdog, WatchdogConfig},
+    sink::{Record, ResultSink, SinkConfig, SCHEMA_VERSION},
+    JsonStatsMonitor, ServerMonitor, ServerResult, SqlsmithMonitor,
+};
+
+/// Kills a campaign given the pid of its fuzzer, -1 if unknown, for its runner to restart it
+pub type RestartFn = Arc<dyn Fn(i32) + Send + Sync>;
+
+/// Runs the collectors of one campaign, without borrowing its monitor
+#[derive(Clone)]
+pub struct Sampler {
+    collectors: Arc<Mutex<Vec<Box<dyn Collector>>>>,
+}
 
 pub struct MonitorManager {
     name: String,
     config: MonitorManagerConfig,
-    dump_writer: csv::Writer<fs::File>,
-    afl_status_monitor: AflStatusMonitor,
-    llvm_cov_monitor: LLVMCoverageMonitor,
+    sinks: Vec<Box<dyn ResultSink>>,
+    collectors: Sampler,
+    /// Metrics of the last sample written to the sinks
+    last_sample: Vec<(String, f64)>,
+    status_monitor: StatusMonitor,
+    server_monitor: ServerMonitor,
+    tracker: Tracker,
+    watchdog: Watchdog,
+    health: Health,
+    restarts: u32,
+    restarting: bool,
 }
 
 #[derive(Clone)]
 pub struct MonitorManagerConfig {
     output_csv: String,
     afl_status_file: String,
-    llvm_exec_path: String,
-    llvm_prof_dir: String,
+    status_kind: StatusKind,
+    collectors: Vec<CollectorConfig>,
+    labels: Vec<(String, String)>,
+    resources_file: String,
+    hooks: Option<Arc<HooksConfig>>,
+    run: String,
+    watchdog: Arc<WatchdogConfig>,
+    restart: Option<RestartFn>,
+    sinks: Vec<SinkConfig>,
 }
 
-pub struct MonitorManagerResult {
-    name: String,
-    timestamp: String,
-    afl_status: AflStautsResult,
-    llvm_cov: LLVMCoverageResult,
+/// Format of the status file a fuzzer writes
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusKind {
+    /// AFL's `fuzzer_stats`
+    #[default]
+    Afl,
+    /// Stats file of the SQLsmith runner
+    Sqlsmith,
+    /// JSON object keyed by the CSV columns, e.g. `{"case_num": 12}`
+    Json,
+    /// No stats, only coverage is collected
+    None,
+}
+
+/// Reads the status file of any supported fuzzer into the AFL columns
+pub enum StatusMonitor {
+    Afl(AflStatusMonitor),
+    Sqlsmith(SqlsmithMonitor),
+    Json(JsonStatsMonitor),
+    None,
+}
+
+impl StatusMonitor {
+    pub fn new(kind: StatusKind, status_file: String) -> Self {
+        match kind {
+            StatusKind::Afl => Self::Afl(AflStatusMonitor::new(status_file)),
+            StatusKind::Sqlsmith => Self::Sqlsmith(SqlsmithMonitor::new(status_file)),
+            StatusKind::Json => Self::Json(JsonStatsMonitor::new(status_file)),
+            StatusKind::None => Self::None,
+        }
+    }
+
+    /// Stats in the AFL columns together with the error count, -1 where unknown
+    pub fn get_result(&self) -> (AflStautsResult, i64) {
+        match self {
+            Self::Afl(monitor) => (monitor.get_result(), -1),
+            // SQLsmith keeps no corpus and no coverage map, its queries stand in for executions.
+            Self::Sqlsmith(monitor) => {
+                let res = monitor.get_result();
+                let status = AflStautsResult {
+                    cycle_num: res.query_num,
+                    crash_num: res.crash_num,
+                    ..AflStautsResult::unknown()
+                };
+                (status, res.error_num)
+            }
+            Self::Json(monitor) => monitor.get_result(),
+            Self::None => (AflStautsResult::unknown(), -1),
+        }
+    }
 }
 
 impl MonitorManager {
-    pub fn new(name: String, config: MonitorManagerConfig) -> Self {
-        let csv_file = fs::File::open(config.output_csv.clone()).expect("Open csv file");
-        let mut wtr = csv::Writer::from_writer(csv_file);
-        wtr.write_record([
-            "name",
-            "timestamp",
-            "case_num",
-            "cycle_num",
-            "crash_num",
-            "edge_covered",
-            "edge_percent",
-            "region_cov_percent",
-            "func_cov_percent",
-            "line_cov_percent",
-            "branch_cov_percent",
-        ])
-        .expect("Failed to write column header to csv file");
-        wtr.flush()
-            .expect("Failed to flush column header to csv file");
+    pub fn new(
+        name: String,
+        config: MonitorManagerConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let collectors = config
+            .collectors
+            .iter()
+            .map(|c| c.open())
+            .collect::<Vec<_>>();
+        let columns = collectors
+            .iter()
+            .flat_map(|c| c.columns())
+            .collect::<Vec<_>>();
+        let mut sinks = vec![SinkConfig::Csv(config.output_csv.clone()).open(&columns)?];
+        for sink in &config.sinks {
+            sinks.push(sink.open(&columns)?);
+        }
 
-        Self {
+        Ok(Self {
             name,
             config: config.clone(),
-            dump_writer: wtr,
-            afl_status_monitor: AflStatusMonitor::new(config.afl_status_file),
-            llvm_cov_monitor: LLVMCoverageMonitor::new(config.llvm_exec_path, config.llvm_prof_dir),
-        }
+            sinks,
+            collectors: Sampler {
+                collectors: Arc::new(Mutex::new(collectors)),
+            },
+            last_sample: vec![],
+            status_monitor: StatusMonitor::new(config.status_kind, config.afl_status_file),
+            server_monitor: ServerMonitor::new(config.resources_file),
+            tracker: Tracker::default(),
+            watchdog: Watchdog::new(chrono::Local::now().timestamp()),
+            health: Health::default(),
+            restarts: 0,
+            restarting: false,
+        })
     }
 
-    pub fn get_result(&self) -> MonitorManagerResult {
-        let now = chrono::Local::now().format(DATETIME_FORMAT_STR).to_string();
-        MonitorManagerResult {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn config(&self) -> &MonitorManagerConfig {
+        &self.config
+    }
+
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.config.labels
+    }
+
+    /// Collectors of the campaign, to be run while the monitor is not locked
+    pub fn sampler(&self) -> Sampler {
+        self.collectors.clone()
+    }
+
+    /// Sample every collector and write the sample to every sink.
+    pub fn dump(&mut self) {
+        let metrics = self.collectors.sample();
+        self.record(metrics);
+    }
+
+    /// Write `metrics` to every sink, reporting the sinks that fail on stderr.
+    pub fn record(&mut self, metrics: Vec<(String, f64)>) {
+        let record = Record {
             name: self.name.clone(),
-            timestamp: now,
-            afl_status: self.afl_status_monitor.get_result(),
-            llvm_cov: self.llvm_cov_monitor.get_result(),
+            timestamp: chrono::Local::now().format(DATETIME_FORMAT_STR).to_string(),
+            metrics,
+            health: self.health,
+            schema_version: SCHEMA_VERSION,
+        };
+
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.write(&record) {
+                eprintln!("Cannot record a sample of {}: {}", self.name, e);
+            }
+        }
+        self.last_sample = record.metrics;
+    }
+
+    /// Metrics of the last sample, empty before the first one
+    pub fn last_sample(&self) -> &[(String, f64)] {
+        &self.last_sample
+    }
+
+    /// Sample the stats and the server, judging the health of the campaign and
+    /// firing the hooks for whatever changed since last time.
+    pub fn check(&mut self) {
+        let (status, _) = self.status_monitor.get_result();
+        let server = self.server_monitor.get_result();
+        let now = chrono::Local::now();
+
+        self.judge(&status, &server, now.timestamp());
+        self.fire_hooks(&status, &server, now);
+    }
+
+    /// Take the last sample once the fuzzer has exited.
+    ///
+    /// Its final finds still fire the hooks, its health stays as last judged while it ran.
+    pub fn finish(&mut self) {
+        let (status, _) = self.status_monitor.get_result();
+        let server = self.server_monitor.get_result();
+
+        self.fire_hooks(&status, &server, chrono::Local::now());
+        self.dump();
+    }
+
+    fn judge(&mut self, status: &AflStautsResult, server: &ServerResult, now: i64) {
+        // Fuzzers without a `last_update` stat are as fresh as their stats file.
+        let updated_at = match status.last_update {
+            -1 => fs::metadata(&self.config.afl_status_file)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(-1, |d| d.as_secs() as i64),
+            last_update => last_update,
+        };
+        let vitals = Vitals {
+            execs: status.cycle_num,
+            execs_per_sec: status.execs_per_sec,
+            updated_at,
+            fuzzer_alive: u32::try_from(status.fuzzer_pid)
+                .ok()
+                .map(campaign::pid_alive),
+            server_up: server.port.map(|_| server.up),
+        };
+        self.health = self.watchdog.check(&self.config.watchdog, &vitals, now);
+
+        if let Some(kill) = &self.config.restart {
+            if self.health >= Health::Stalled
+                && !self.restarting
+                && self.restarts < self.config.watchdog.max_restarts
+            {
+                eprintln!("{} is {}, restarting it", self.name, self.health);
+                self.restarting = true;
+                self.restarts += 1;
+                kill(status.fuzzer_pid);
+            }
+        }
+    }
+
+    fn fire_hooks(
+        &mut self,
+        status: &AflStautsResult,
+        server: &ServerResult,
+        now: chrono::DateTime<chrono::Local>,
+    ) {
+        let Some(hooks) = self.config.hooks.clone() else {
+            return;
+        };
+
+        let sample = Sample {
+            crash_num: status.crash_num,
+            hang_num: status.hang_num,
+            execs: status.cycle_num,
+            server_up: server.port.map(|_| server.up),
+            port: server.port,
+        };
+        for kind in self
+            .tracker
+            .update(&sample, now.timestamp(), hooks.stall_secs())
+        {
+            hooks.fire(&Event {
+                run: self.config.run.clone(),
+                campaign: self.name.clone(),
+                timestamp: now.format(DATETIME_FORMAT_STR).to_string(),
+                kind,
+            });
+        }
+    }
+
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// Whether the watchdog killed the fuzzer for its runner to restart it,
+    /// giving the restarted fuzzer a fresh grace period if so.
+    pub fn take_restart(&mut self) -> bool {
+        if !self.restarting {
+            return false;
         }
+
+        self.restarting = false;
+        self.watchdog = Watchdog::new(chrono::Local::now().timestamp());
+        true
     }
+}
 
-    pub fn dump_to_csv(&mut self) {
-        let res = self.get_result();
-        self.dump_writer
-            .write_record(&[
-                res.name,
-                res.timestamp,
-                res.afl_status.case_num.to_string(),
-                res.afl_status.cycle_num.to_string(),
-                res.afl_status.crash_num.to_string(),
-                res.afl_status.edge_covered.to_string(),
-                res.afl_status.edge_percent.to_string(),
-                res.llvm_cov.region_cov.percent.to_string(),
-                res.llvm_cov.func_cov.percent.to_string(),
-                res.llvm_cov.line_cov.percent.to_string(),
-                res.llvm_cov.branch_cov.percent.to_string(),
-            ])
-            .expect("Failed to write record to csv file");
-
-        self.dump_writer
-            .flush()
-            .expect("Failed to flush record to csv file");
+impl Sampler {
+    /// Current metrics of every collector, in the order of their columns
+    pub fn sample(&self) -> Vec<(String, f64)> {
+        self.collectors
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|c| c.columns().into_iter().zip(c.collect()))
+            .collect()
     }
 }
 
 impl MonitorManagerConfig {
-    pub fn new(
-        output_csv: String,
-        afl_status_file: String,
-        llvm_exec_path: String,
-        llvm_prof_dir: String,
-    ) -> Self {
+    /// Sample into `output_csv`, watching the fuzzer through its stats in `afl_status_file`.
+    pub fn new(output_csv: String, afl_status_file: String) -> Self {
         Self {
             output_csv,
             afl_status_file,
-            llvm_exec_path,
-            llvm_prof_dir,
+            status_kind: StatusKind::default(),
+            collectors: vec![],
+            labels: vec![],
+            resources_file: String::new(),
+            hooks: None,
+            run: String::new(),
+            watchdog: Arc::default(),
+            restart: None,
+            sinks: vec![],
         }
     }
+
+    /// Read the status file in the format of another fuzzer than AFL.
+    pub fn status_kind(mut self, status_kind: StatusKind) -> Self {
+        self.status_kind = status_kind;
+        self
+    }
+
+    /// Also sample the metrics of `collector`, in the columns after those added before.
+    pub fn collector(mut self, collector: CollectorConfig) -> Self {
+        self.collectors.push(collector);
+        self
+    }
+
+    /// Describe the campaign by `name`, e.g. its fuzzer, to whoever exports its samples.
+    pub fn label(mut self, name: &str, value: String) -> Self {
+        self.labels.push((name.to_string(), value));
+        self
+    }
+
+    /// Check whether the database server recorded in `resources.json` still listens.
+    pub fn resources_file(mut self, resources_file: String) -> Self {
+        self.resources_file = resources_file;
+        self
+    }
+
+    /// Fire `hooks` on new crashes, hangs, stalls and server outages of a campaign of `run`.
+    pub fn hooks(mut self, hooks: Arc<HooksConfig>, run: String) -> Self {
+        self.hooks = Some(hooks);
+        self.run = run;
+        self
+    }
+
+    /// Judge the health of the campaign by `watchdog`, killing it with `restart`
+    /// once stalled or dead if the watchdog restarts campaigns.
+    pub fn watchdog(mut self, watchdog: Arc<WatchdogConfig>, restart: RestartFn) -> Self {
+        self.restart = watchdog.restart.then_some(restart);
+        self.watchdog = watchdog;
+        self
+    }
+
+    /// Also write every sample to `sink`, besides the CSV.
+    pub fn sink(mut self, sink: SinkConfig) -> Self {
+        self.sinks.push(sink);
+        self
+    }
 }
@@ -1,8 +1,37 @@
-pub use manager::{MonitorManager, MonitorManagerConfig};
+pub use manager::{
+    MonitorManager, MonitorManagerConfig, RestartFn, Sampler, StatusKind, StatusMonitor,
+};
 
 pub use afl_status::{AflStatusMonitor, AflStautsResult};
+pub use cgroup::{CgroupMonitor, CgroupResult};
+pub use collector::{
+    Collector, CollectorConfig, CollectorsConfig, CommandConfig, ValidityConfig, BUILTIN_COLLECTORS,
+};
+pub use command::CommandMonitor;
+pub use corpus::{CorpusMonitor, CorpusResult};
+pub use disk::DiskMonitor;
+pub use health::{Health, WatchdogConfig};
+pub use json_stats::JsonStatsMonitor;
 pub use llvm_cov::{LLVMCoverageMonitor, LLVMCoverageResult};
+pub use server::{ServerMonitor, ServerResult};
+pub use sink::{
+    CsvSink, JsonlSink, Record, ResultSink, SinkConfig, SinkKind, SqliteSink, SCHEMA_VERSION,
+};
+pub use sqlsmith::{SqlsmithMonitor, SqlsmithResult};
+pub use validity::{Reference, ReferenceConfig, ValidityMonitor, ValidityResult};
 
 mod afl_status;
+mod cgroup;
+mod collector;
+mod command;
+mod corpus;
+mod disk;
+mod health;
+mod json_stats;
 mod llvm_cov;
 mod manager;
+mod server;
+mod sink;
+mod sql;
+mod sqlsmith;
+mod validity;
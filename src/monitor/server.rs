@@ -0,0 +1,34 @@
+use std::fs;
+
+use crate::runner::slot;
+
+/// Checks whether the database server of a campaign accepts connections
+pub struct ServerMonitor {
+    /// `resources.json` the runner records the server port in
+    pub resources_file: String,
+}
+
+pub struct ServerResult {
+    /// Port of the server, absent for targets without one or before it was allocated
+    pub port: Option<u16>,
+    pub up: bool,
+}
+
+impl ServerMonitor {
+    pub fn new(resources_file: String) -> Self {
+        Self { resources_file }
+    }
+
+    pub fn get_result(&self) -> ServerResult {
+        let port = fs::read_to_string(&self.resources_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|resources| resources["port"].as_u64())
+            .and_then(|port| u16::try_from(port).ok());
+
+        ServerResult {
+            port,
+            up: port.is_some_and(slot::port_in_use),
+        }
+    }
+}
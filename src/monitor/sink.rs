@@ -0,0 +1,363 @@
+use std::{fs, io::Write, path::Path, str::FromStr, time::Duration};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::Campaign;
+
+use super::Health;
+
+/// Version of the sample layout, bumped whenever it changes.
+///
+/// Every sample carries it in its `schema_version` field, CSV files without one are version 1.
+/// Version 2 has fixed columns, from version 3 on the metrics depend on the collectors.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// One sample of a campaign: the metrics of all its collectors, -1 where unknown
+#[derive(Debug)]
+pub struct Record {
+    pub name: String,
+    pub timestamp: String,
+    pub metrics: Vec<(String, f64)>,
+    pub health: Health,
+    pub schema_version: u32,
+}
+
+/// Destination the samples of a campaign are written to
+pub trait ResultSink: Send {
+    fn write(&mut self, record: &Record) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Sinks written besides the monitor CSV, which `status` and `report` read
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    /// `monitor.jsonl` next to the CSV, one JSON object per sample
+    Jsonl,
+    /// The history database shared by all runs
+    Sqlite,
+}
+
+/// Where a sink of one campaign writes to
+#[derive(Clone)]
+pub enum SinkConfig {
+    Csv(String),
+    Jsonl(String),
+    Sqlite { db: String, campaign: Campaign },
+}
+
+/// Appends to a monitor CSV
+pub struct CsvSink {
+    writer: csv::Writer<fs::File>,
+}
+
+/// Appends to a JSON Lines file
+pub struct JsonlSink {
+    file: fs::File,
+}
+
+/// Inserts into the `samples` and `metrics` tables of a SQLite database
+pub struct SqliteSink {
+    conn: Connection,
+    run: String,
+}
+
+impl FromStr for SinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(Self::Jsonl),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(format!("Unknown sink {}, expected jsonl or sqlite", s)),
+        }
+    }
+}
+
+impl SinkConfig {
+    /// Open the sink for samples with the given metrics.
+    pub fn open(
+        &self,
+        metrics: &[String],
+    ) -> Result<Box<dyn ResultSink>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Self::Csv(path) => Box::new(CsvSink::open(path, metrics)?),
+            Self::Jsonl(path) => Box::new(JsonlSink::open(path)?),
+            Self::Sqlite { db, campaign } => Box::new(SqliteSink::open(db, campaign)?),
+        })
+    }
+}
+
+impl Record {
+    /// Columns of a sample with `metrics`, in the order of `fields`
+    pub fn columns(metrics: &[String]) -> Vec<String> {
+        let mut columns = vec!["name".to_string(), "timestamp".to_string()];
+        columns.extend(metrics.iter().cloned());
+        columns.extend(["health".to_string(), "schema_version".to_string()]);
+        columns
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec![self.name.clone(), self.timestamp.clone()];
+        fields.extend(self.metrics.iter().map(|(_, v)| v.to_string()));
+        fields.extend([self.health.to_string(), self.schema_version.to_string()]);
+        fields
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        object.insert("name".to_string(), self.name.clone().into());
+        object.insert("timestamp".to_string(), self.timestamp.clone().into());
+        for (metric, value) in &self.metrics {
+            object.insert(metric.clone(), (*value).into());
+        }
+        object.insert("health".to_string(), self.health.to_string().into());
+        object.insert("schema_version".to_string(), self.schema_version.into());
+        object.into()
+    }
+}
+
+impl CsvSink {
+    /// Append samples with `metrics` to the CSV at `path`, created with its header if new.
+    ///
+    /// An existing file must have the same columns, so a restarted monitor continues its history.
+    pub fn open(path: &str, metrics: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let columns = Record::columns(metrics);
+        if fs::metadata(path).is_ok_and(|m| m.len() > 0) {
+            let header = csv::Reader::from_path(path)?.headers()?.clone();
+            if !header.iter().eq(columns.iter()) {
+                return Err(format!(
+                    "{} does not have the columns of schema version {} with the configured \
+                     collectors, move it away to start over",
+                    path, SCHEMA_VERSION
+                )
+                .into());
+            }
+
+            let file = fs::OpenOptions::new().append(true).open(path)?;
+            return Ok(Self {
+                writer: csv::Writer::from_writer(file),
+            });
+        }
+
+        create_parent(path)?;
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(&columns)?;
+        writer.flush()?;
+
+        Ok(Self { writer })
+    }
+}
+
+impl ResultSink for CsvSink {
+    fn write(&mut self, record: &Record) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.write_record(record.fields())?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl JsonlSink {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        create_parent(path)?;
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self { file })
+    }
+}
+
+impl ResultSink for JsonlSink {
+    fn write(&mut self, record: &Record) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(self.file, "{}", record.to_json())?;
+
+        Ok(())
+    }
+}
+
+impl SqliteSink {
+    /// Open the history database at `db`, creating its tables, and record `campaign` in it.
+    pub fn open(db: &str, campaign: &Campaign) -> Result<Self, Box<dyn std::error::Error>> {
+        create_parent(db)?;
+        let conn = Connection::open(db)?;
+        // Campaigns of concurrent runs share the database.
+        conn.busy_timeout(Duration::from_secs(10))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS campaigns (
+                run TEXT NOT NULL,
+                campaign TEXT NOT NULL,
+                experiment TEXT NOT NULL,
+                fuzzer TEXT NOT NULL,
+                target TEXT NOT NULL,
+                version TEXT NOT NULL,
+                trial INTEGER NOT NULL,
+                duration_secs INTEGER,
+                PRIMARY KEY (run, campaign)
+            );
+            CREATE TABLE IF NOT EXISTS samples (
+                id INTEGER PRIMARY KEY,
+                run TEXT NOT NULL,
+                campaign TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                health TEXT,
+                schema_version INTEGER NOT NULL,
+                FOREIGN KEY (run, campaign) REFERENCES campaigns (run, campaign)
+            );
+            CREATE TABLE IF NOT EXISTS metrics (
+                sample INTEGER NOT NULL REFERENCES samples (id),
+                name TEXT NOT NULL,
+                value REAL NOT NULL,
+                PRIMARY KEY (sample, name)
+            );
+            CREATE INDEX IF NOT EXISTS samples_campaign ON samples (run, campaign);",
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO campaigns VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                campaign.run,
+                campaign.id(),
+                campaign.experiment,
+                campaign.fuzzer,
+                campaign.target,
+                campaign.version,
+                campaign.trial,
+                campaign.duration_secs,
+            ],
+        )?;
+
+        Ok(Self {
+            conn,
+            run: campaign.run.clone(),
+        })
+    }
+}
+
+impl ResultSink for SqliteSink {
+    fn write(&mut self, record: &Record) -> Result<(), Box<dyn std::error::Error>> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO samples (run, campaign, timestamp, health, schema_version)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                self.run,
+                record.name,
+                record.timestamp,
+                record.health.to_string(),
+                record.schema_version,
+            ],
+        )?;
+        let sample = tx.last_insert_rowid();
+        for (metric, value) in &record.metrics {
+            tx.execute(
+                "INSERT INTO metrics VALUES (?1, ?2, ?3)",
+                rusqlite::params![sample, metric, value],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+fn create_parent(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> Vec<String> {
+        vec!["edge_covered".to_string(), "line_cov_percent".to_string()]
+    }
+
+    fn record(name: &str) -> Record {
+        Record {
+            name: name.to_string(),
+            timestamp: "2024-01-31 09:35:00".to_string(),
+            metrics: metrics().into_iter().zip([800.0, 10.25]).collect(),
+            health: Health::Healthy,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_csv_sink() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = crate::utils::test_dir("csv_sink");
+        let path = dir.join("trial1/monitor.csv").display().to_string();
+
+        CsvSink::open(&path, &metrics())?.write(&record("first"))?;
+        // A restarted monitor keeps the rows written before.
+        CsvSink::open(&path, &metrics())?.write(&record("second"))?;
+        // Other collectors would mix up the columns.
+        assert!(CsvSink::open(&path, &metrics()[..1]).is_err());
+
+        let mut rdr = csv::Reader::from_path(&path)?;
+        assert_eq!(rdr.headers()?, Record::columns(&metrics()));
+        let rows = rdr.records().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[1][0], "second");
+        assert_eq!(&rows[1][3], "10.25");
+        assert_eq!(&rows[1][4], "healthy");
+
+        fs::write(
+            &path,
+            "name,timestamp,case_num\nsquirrel-pg,2024-01-31 09:35:00,12\n",
+        )?;
+        assert!(CsvSink::open(&path, &metrics()).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqlite_sink() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = crate::utils::test_dir("sqlite_sink");
+        let db = dir.join("history.sqlite").display().to_string();
+        let campaign = Campaign {
+            experiment: "sinks".to_string(),
+            run: "20240131-093000-sinks".to_string(),
+            fuzzer: "squirrel".to_string(),
+            target: "sqlite".to_string(),
+            version: "3.42.0".to_string(),
+            trial: 1,
+            duration_secs: Some(3600),
+        };
+
+        SqliteSink::open(&db, &campaign)?.write(&record(&campaign.id()))?;
+        let later = Campaign {
+            run: "20240201-093000-sinks".to_string(),
+            ..campaign.clone()
+        };
+        let mut sink = SqliteSink::open(&db, &later)?;
+        sink.write(&record(&later.id()))?;
+        sink.write(&record(&later.id()))?;
+
+        let conn = Connection::open(&db)?;
+        let runs: i64 = conn.query_row("SELECT COUNT(*) FROM campaigns", [], |r| r.get(0))?;
+        assert_eq!(runs, 2);
+        let samples: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM samples JOIN campaigns USING (run, campaign)
+             WHERE experiment = 'sinks' AND run = ?1",
+            [&later.run],
+            |r| r.get(0),
+        )?;
+        assert_eq!(samples, 2);
+        let edges: f64 = conn.query_row(
+            "SELECT value FROM metrics WHERE name = 'edge_covered' LIMIT 1",
+            [],
+            |r| r.get(0),
+        )?;
+        assert_eq!(edges, 800.0);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
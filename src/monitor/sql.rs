@@ -0,0 +1,320 @@
+use std::collections::BTreeSet;
+
+/// Statement types counted separately, any other statement counts as `other`
+pub const STATEMENT_TYPES: [&str; 7] = [
+    "select", "insert", "update", "delete", "create", "drop", "alter",
+];
+
+/// Keywords of SQLite, which the other targets share for the most part
+const KEYWORDS: [&str; 147] = [
+    "ABORT",
+    "ACTION",
+    "ADD",
+    "AFTER",
+    "ALL",
+    "ALTER",
+    "ALWAYS",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "ATTACH",
+    "AUTOINCREMENT",
+    "BEFORE",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "CONFLICT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "DATABASE",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DEFERRED",
+    "DELETE",
+    "DESC",
+    "DETACH",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "EACH",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXCLUSIVE",
+    "EXISTS",
+    "EXPLAIN",
+    "FAIL",
+    "FILTER",
+    "FIRST",
+    "FOLLOWING",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GENERATED",
+    "GLOB",
+    "GROUP",
+    "GROUPS",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "IMMEDIATE",
+    "IN",
+    "INDEX",
+    "INDEXED",
+    "INITIALLY",
+    "INNER",
+    "INSERT",
+    "INSTEAD",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATCH",
+    "MATERIALIZED",
+    "NATURAL",
+    "NO",
+    "NOT",
+    "NOTHING",
+    "NOTNULL",
+    "NULL",
+    "NULLS",
+    "OF",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OTHERS",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PLAN",
+    "PRAGMA",
+    "PRECEDING",
+    "PRIMARY",
+    "QUERY",
+    "RAISE",
+    "RANGE",
+    "RECURSIVE",
+    "REFERENCES",
+    "REGEXP",
+    "REINDEX",
+    "RELEASE",
+    "RENAME",
+    "REPLACE",
+    "RESTRICT",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROW",
+    "ROWS",
+    "SAVEPOINT",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "TEMP",
+    "TEMPORARY",
+    "THEN",
+    "TIES",
+    "TO",
+    "TRANSACTION",
+    "TRIGGER",
+    "UNBOUNDED",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VIEW",
+    "VIRTUAL",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHOUT",
+];
+
+/// Shape of one statement
+#[derive(Debug, PartialEq)]
+pub struct StatementStats {
+    /// One of `STATEMENT_TYPES`, or `other`
+    pub kind: &'static str,
+    /// Deepest nesting of parentheses, e.g. 2 for a subquery in an `IN (...)` list
+    pub depth: usize,
+    /// Keywords used, upper case
+    pub keywords: BTreeSet<&'static str>,
+}
+
+/// Statements of a test case, split at the semicolons outside of quotes and comments
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = vec![];
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                // A doubled quote escapes itself and just reopens the literal.
+                for q in chars.by_ref() {
+                    current.push(q);
+                    if q == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for q in chars.by_ref() {
+                    if q == '\n' {
+                        current.push(q);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for q in chars.by_ref() {
+                    if last == '*' && q == '/' {
+                        break;
+                    }
+                    last = q;
+                }
+                current.push(' ');
+            }
+            ';' => {
+                push_statement(&mut statements, &current);
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    push_statement(&mut statements, &current);
+
+    statements
+}
+
+/// Type, nesting depth and keywords of a statement, ignoring quoted text
+pub fn analyze(statement: &str) -> StatementStats {
+    // Words outside quotes, with the parenthesis depth they appear at
+    let mut words: Vec<(String, usize)> = vec![];
+    let mut depth = 0;
+    let mut max_depth = 0;
+    let mut word = String::new();
+    let mut chars = statement.chars();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            words.push((std::mem::take(&mut word).to_uppercase(), depth));
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                chars.by_ref().find(|q| *q == c);
+            }
+            '(' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push((word.to_uppercase(), depth));
+    }
+
+    // A common table expression precedes the statement it belongs to.
+    let verb = match words.first() {
+        Some((first, _)) if first == "WITH" => words
+            .iter()
+            .find(|(w, d)| {
+                *d == 0 && matches!(w.as_str(), "SELECT" | "INSERT" | "UPDATE" | "DELETE")
+            })
+            .map(|(w, _)| w.as_str()),
+        first => first.map(|(w, _)| w.as_str()),
+    };
+    let kind = STATEMENT_TYPES
+        .iter()
+        .find(|t| verb.is_some_and(|v| v.eq_ignore_ascii_case(t)))
+        .copied()
+        .unwrap_or("other");
+    let keywords = words
+        .iter()
+        .filter_map(|(w, _)| KEYWORDS.iter().find(|k| *k == w).copied())
+        .collect();
+
+    StatementStats {
+        kind,
+        depth: max_depth,
+        keywords,
+    }
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let stats = analyze(
+            "WITH x AS (SELECT 1) INSERT INTO t SELECT * FROM x WHERE a IN (SELECT (b) FROM 'from')",
+        );
+        assert_eq!(stats.kind, "insert");
+        assert_eq!(stats.depth, 2);
+        assert_eq!(
+            stats.keywords.into_iter().collect::<Vec<_>>(),
+            vec!["AS", "FROM", "IN", "INSERT", "INTO", "SELECT", "WHERE", "WITH"]
+        );
+        assert_eq!(analyze("pragma integrity_check").kind, "other");
+        assert_eq!(analyze("select 1").kind, "select");
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let statements = split_statements(
+            "CREATE TABLE t (a TEXT); -- one; two\n\
+             INSERT INTO t VALUES ('x;y'), ('it''s');\n\
+             /* ; */ SELECT \"a;\" FROM t;;  ",
+        );
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE t (a TEXT)",
+                "INSERT INTO t VALUES ('x;y'), ('it''s')",
+                "SELECT \"a;\" FROM t",
+            ]
+        );
+    }
+}
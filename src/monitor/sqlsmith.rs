@@ -0,0 +1,55 @@
+use std::fs;
+
+/// Reads the stats file the SQLsmith runner keeps up to date
+pub struct SqlsmithMonitor {
+    pub stats_file: String,
+}
+
+#[derive(Default)]
+pub struct SqlsmithResult {
+    pub query_num: i64,
+    pub error_num: i64,
+    pub crash_num: i64,
+}
+
+impl SqlsmithMonitor {
+    pub fn new(stats_file: String) -> Self {
+        Self { stats_file }
+    }
+
+    pub fn get_result(&self) -> SqlsmithResult {
+        let content = fs::read_to_string(&self.stats_file).unwrap_or_default();
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> SqlsmithResult {
+        let item = |key: &str| {
+            content
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(k, _)| k.trim() == key)
+                .and_then(|(_, v)| v.trim().parse().ok())
+                .unwrap_or(-1)
+        };
+
+        SqlsmithResult {
+            query_num: item("queries"),
+            error_num: item("errors"),
+            crash_num: item("crashes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stats() {
+        let res = SqlsmithMonitor::parse("start_time : 1\nqueries    : 120\nerrors     : 7\n");
+
+        assert_eq!(res.query_num, 120);
+        assert_eq!(res.error_num, 7);
+        assert_eq!(res.crash_num, -1);
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use rusqlite::{limits::Limit, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::{sql, Collector};
+use crate::constants::DATETIME_FORMAT_STR;
+
+/// Longest a reference database may spend on one statement
+const STATEMENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// SQLite errors of statements that do not parse
+const SQLITE_SYNTAX_ERRORS: [&str; 3] = ["syntax error", "incomplete input", "unrecognized token"];
+
+/// Database server a sampled queue is replayed against, see `config/collectors.toml`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ReferenceConfig {
+    /// Shell command executing the statement on its stdin, exiting non-zero with the error on stderr
+    pub command: String,
+
+    /// Parts of the errors of statements that do not parse
+    #[serde(default = "default_syntax_errors")]
+    pub syntax_errors: Vec<String>,
+}
+
+/// Where sampled statements are replayed
+#[derive(Clone)]
+pub enum Reference {
+    /// A fresh in-memory SQLite database per test case
+    Sqlite,
+    /// A reference server reached through a client command
+    Command(ReferenceConfig),
+}
+
+/// Replays a sample of a fuzzer's queue, measuring how many of its statements are valid
+pub struct ValidityMonitor {
+    pub queue_dir: String,
+    pub reference: Reference,
+    /// Test cases replayed per sample
+    pub sample: usize,
+    /// JSON Lines file the error categories of every sample are appended to
+    pub log_file: String,
+}
+
+/// Outcome of replaying the statements of one sample
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct ValidityResult {
+    pub statements: i64,
+    pub parsed: i64,
+    pub executed: i64,
+    /// Failed statements per error category
+    pub errors: BTreeMap<String, i64>,
+}
+
+/// Result of one statement
+enum Outcome {
+    Executed,
+    Failed(String),
+    SyntaxError(String),
+}
+
+fn default_syntax_errors() -> Vec<String> {
+    vec!["syntax error".to_string()]
+}
+
+impl ValidityResult {
+    pub fn parse_percent(&self) -> f64 {
+        percent(self.parsed, self.statements)
+    }
+
+    pub fn exec_percent(&self) -> f64 {
+        percent(self.executed, self.statements)
+    }
+
+    fn record(&mut self, outcome: Outcome) {
+        self.statements += 1;
+        match outcome {
+            Outcome::Executed => {
+                self.parsed += 1;
+                self.executed += 1;
+            }
+            Outcome::Failed(error) => {
+                self.parsed += 1;
+                *self.errors.entry(category(&error)).or_default() += 1;
+            }
+            Outcome::SyntaxError(error) => {
+                *self.errors.entry(category(&error)).or_default() += 1;
+            }
+        }
+    }
+}
+
+impl ValidityMonitor {
+    pub fn new(queue_dir: String, reference: Reference, sample: usize, log_file: String) -> Self {
+        Self {
+            queue_dir,
+            reference,
+            sample,
+            log_file,
+        }
+    }
+
+    /// Replay up to `sample` test cases spread evenly over the queue, oldest first.
+    ///
+    /// `None` before the fuzzer queued anything.
+    pub fn get_result(&self) -> Option<ValidityResult> {
+        let mut cases = fs::read_dir(&self.queue_dir)
+            .ok()?
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+            .map(|e| e.path())
+            .collect::<Vec<_>>();
+        if cases.is_empty() || self.sample == 0 {
+            return None;
+        }
+        cases.sort();
+        let step = cases.len().div_ceil(self.sample);
+
+        let mut res = ValidityResult::default();
+        for case in cases.iter().step_by(step) {
+            let Ok(content) = fs::read(case) else {
+                continue;
+            };
+            let statements = sql::split_statements(&String::from_utf8_lossy(&content));
+            match &self.reference {
+                Reference::Sqlite => replay_sqlite(&statements, &mut res),
+                Reference::Command(config) => {
+                    for statement in &statements {
+                        res.record(run_command(config, statement));
+                    }
+                }
+            }
+        }
+
+        Some(res)
+    }
+
+    fn log(&self, res: &ValidityResult) -> Result<(), Box<dyn std::error::Error>> {
+        let mut entry = serde_json::to_value(res)?;
+        let now = chrono::Local::now().format(DATETIME_FORMAT_STR).to_string();
+        entry["timestamp"] = now.into();
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_file)?;
+        writeln!(file, "{}", entry)?;
+
+        Ok(())
+    }
+}
+
+impl Collector for ValidityMonitor {
+    fn columns(&self) -> Vec<String> {
+        ["replayed_statements", "parse_percent", "exec_percent"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    fn collect(&self) -> Vec<f64> {
+        let Some(res) = self.get_result() else {
+            return vec![-1.0; 3];
+        };
+        if let Err(e) = self.log(&res) {
+            eprintln!("Cannot log the validity of {}: {}", self.queue_dir, e);
+        }
+
+        vec![
+            res.statements as f64,
+            res.parse_percent(),
+            res.exec_percent(),
+        ]
+    }
+}
+
+/// Run the statements of one test case in order on a fresh in-memory database.
+fn replay_sqlite(statements: &[String], res: &mut ValidityResult) {
+    let Ok(conn) = Connection::open_in_memory() else {
+        return;
+    };
+    // Neither ATTACH nor VACUUM INTO may write files from fuzzer input.
+    conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+
+    for statement in statements {
+        let deadline = Instant::now() + STATEMENT_TIMEOUT;
+        conn.progress_handler(10_000, Some(move || Instant::now() > deadline));
+
+        let outcome = match conn.prepare(statement).map_err(|e| sqlite_message(&e)) {
+            Err(e) if is_syntax_error(&e, &SQLITE_SYNTAX_ERRORS) => Outcome::SyntaxError(e),
+            Err(e) => Outcome::Failed(e),
+            Ok(mut stmt) => {
+                let mut rows = stmt.raw_query();
+                loop {
+                    match rows.next() {
+                        Ok(Some(_)) => continue,
+                        Ok(None) => break Outcome::Executed,
+                        Err(e) => break Outcome::Failed(e.to_string()),
+                    }
+                }
+            }
+        };
+        res.record(outcome);
+    }
+}
+
+/// Error message of SQLite, without the statement rusqlite appends to syntax errors
+fn sqlite_message(e: &rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::SqlInputError { msg, .. } => msg.clone(),
+        e => e.to_string(),
+    }
+}
+
+/// Run one statement through the client command of a reference server.
+fn run_command(config: &ReferenceConfig, statement: &str) -> Outcome {
+    let child = Command::new("/bin/sh")
+        .args(["-c", &config.command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return Outcome::Failed(format!("cannot run the reference client: {}", e)),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // A client exiting early on a bad statement closes its stdin.
+        let _ = writeln!(stdin, "{};", statement);
+    }
+
+    let deadline = Instant::now() + STATEMENT_TIMEOUT;
+    while child.try_wait().ok().flatten().is_none() {
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Outcome::Failed("timeout".to_string());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let Ok(output) = child.wait_with_output() else {
+        return Outcome::Failed("reference client vanished".to_string());
+    };
+    if output.status.success() {
+        return Outcome::Executed;
+    }
+
+    let error = String::from_utf8_lossy(&output.stderr).to_string();
+    if is_syntax_error(&error, &config.syntax_errors) {
+        Outcome::SyntaxError(error)
+    } else {
+        Outcome::Failed(error)
+    }
+}
+
+fn is_syntax_error<S: AsRef<str>>(error: &str, syntax_errors: &[S]) -> bool {
+    syntax_errors.iter().any(|s| error.contains(s.as_ref()))
+}
+
+/// Error message without the names and values of the statement, e.g. `no such table: ?`
+fn category(error: &str) -> String {
+    let mut line = error.lines().next().unwrap_or_default().trim();
+    // Clients prefix their errors, e.g. `ERROR:  relation "t" does not exist`.
+    if line.starts_with("ERROR") || line.starts_with("Error") {
+        line = line.split_once(':').map_or(line, |(_, rest)| rest.trim());
+    }
+
+    let mut category = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if matches!(c, '\'' | '"' | '`') {
+            chars.by_ref().find(|q| *q == c);
+            category.push('?');
+        } else {
+            category.push(c);
+        }
+    }
+    // A single trailing name, as in `no such column: a`
+    if let Some((head, name)) = category.rsplit_once(": ") {
+        if !name.is_empty() && !name.contains(' ') {
+            category = format!("{}: ?", head);
+        }
+    }
+
+    category
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return -1.0;
+    }
+
+    100.0 * part as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_validity() -> Result<(), Box<dyn std::error::Error>> {
+        let queue_dir = crate::utils::test_dir("validity");
+        fs::write(
+            queue_dir.join("id:000000"),
+            "CREATE TABLE t (a); INSERT INTO t VALUES (1); SELEC 1;",
+        )?;
+        fs::write(
+            queue_dir.join("id:000001"),
+            "SELECT b FROM missing; SELECT b FROM t; ATTACH 'x.db' AS x;",
+        )?;
+
+        let monitor = ValidityMonitor::new(
+            queue_dir.display().to_string(),
+            Reference::Sqlite,
+            10,
+            queue_dir.join("validity.jsonl").display().to_string(),
+        );
+        let res = monitor.get_result().unwrap();
+        assert_eq!(res.statements, 6);
+        assert_eq!(res.parsed, 5);
+        assert_eq!(res.executed, 2);
+        assert_eq!(res.errors["near ?: syntax error"], 1);
+        assert_eq!(res.errors["no such table: ?"], 2);
+        assert_eq!(res.exec_percent(), 100.0 / 3.0);
+
+        assert_eq!(
+            category("ERROR:  relation \"t1\" does not exist\nLINE 1: ..."),
+            "relation ? does not exist"
+        );
+
+        fs::remove_dir_all(&queue_dir)?;
+        Ok(())
+    }
+}
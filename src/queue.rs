@@ -0,0 +1,349 @@
+use std::{fs, path::Path, sync::Arc, thread::JoinHandle, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign::{self, Campaign},
+    monitor::SinkKind,
+    runner::{cpu, slot},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+/// One campaign of a run, waiting for or holding its cores
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    pub campaign: Campaign,
+    pub state: JobState,
+    pub error: Option<String>,
+}
+
+/// Campaigns of a run, persisted to `queue.json` so a restart resumes where it left off
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Queue {
+    pub run: String,
+    pub jobs: Vec<Job>,
+    /// Sinks the samples are written to besides the monitor CSV
+    #[serde(default)]
+    pub sinks: Vec<SinkKind>,
+    /// Address the samples are exported on for Prometheus
+    #[serde(default)]
+    pub metrics: Option<String>,
+}
+
+/// Number of jobs in each state
+#[derive(Default, Debug, PartialEq)]
+pub struct Progress {
+    pub pending: usize,
+    pub running: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+impl Queue {
+    pub fn new(run: &str, campaigns: Vec<Campaign>) -> Self {
+        Self {
+            run: run.to_string(),
+            jobs: campaigns
+                .into_iter()
+                .map(|campaign| Job {
+                    campaign,
+                    state: JobState::Pending,
+                    error: None,
+                })
+                .collect(),
+            sinks: vec![],
+            metrics: None,
+        }
+    }
+
+    pub fn load(run: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content =
+            fs::read_to_string(path(run)).map_err(|_| format!("Run {} has no job queue", run))?;
+
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write the queue through a temporary file, so a crash never leaves it half written.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path(&self.run);
+        let partial = format!("{}.part", path);
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(partial, path)?;
+
+        Ok(())
+    }
+
+    pub fn progress(&self) -> Progress {
+        let mut progress = Progress::default();
+        for job in &self.jobs {
+            match job.state {
+                JobState::Pending => progress.pending += 1,
+                JobState::Running => progress.running += 1,
+                JobState::Done => progress.done += 1,
+                JobState::Failed => progress.failed += 1,
+            }
+        }
+
+        progress
+    }
+
+    /// Put the jobs a previous scheduler left running back in line, returning them.
+    ///
+    /// A campaign cut short cannot be compared with complete ones, so it starts over
+    /// once `schedule` set its directory aside.
+    pub fn requeue_interrupted(&mut self) -> Vec<Campaign> {
+        let mut interrupted = vec![];
+        for job in &mut self.jobs {
+            if job.state == JobState::Running {
+                job.state = JobState::Pending;
+                interrupted.push(job.campaign.clone());
+            }
+        }
+
+        interrupted
+    }
+
+    /// Refuse jobs queued behind campaigns without a time budget, they would wait forever.
+    pub fn check_budget(&self, budget: usize) -> Result<(), String> {
+        let unfinished = self
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.state, JobState::Pending | JobState::Running))
+            .collect::<Vec<_>>();
+        let needed: usize = unfinished
+            .iter()
+            .map(|job| cpu::cores_needed(&job.campaign.target))
+            .sum();
+
+        match unfinished
+            .iter()
+            .find(|job| job.campaign.duration().is_none())
+        {
+            Some(job) if needed > budget => Err(format!(
+                "Campaigns need {} cores but only {} are available, and {} never ends \
+                 to hand its cores over, give every campaign a --duration",
+                needed,
+                budget,
+                job.campaign.id()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn set(&mut self, i: usize, state: JobState, error: Option<String>) {
+        self.jobs[i].state = state;
+        self.jobs[i].error = error;
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} done, {} failed, {} running, {} pending",
+            self.done, self.failed, self.running, self.pending
+        )
+    }
+}
+
+fn path(run: &str) -> String {
+    format!("{}/queue.json", campaign::run_dir(run))
+}
+
+/// Move the directory of an interrupted campaign to `<id>.interrupted`, so it starts over
+/// without losing what it found. Returns where it went, if it had started at all.
+fn set_aside(campaign: &Campaign) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let dir = campaign.dir();
+    if !Path::new(&dir).exists() {
+        return Ok(None);
+    }
+
+    let aside = (1..)
+        .map(|n| match n {
+            1 => format!("{}.interrupted", dir),
+            n => format!("{}.interrupted{}", dir, n),
+        })
+        .find(|aside| !Path::new(aside).exists())
+        .unwrap();
+    fs::rename(&dir, &aside)?;
+
+    Ok(Some(aside))
+}
+
+/// Run the pending jobs of `queue` with `launch`, as many at once as the core budget allows.
+///
+/// Jobs start in queue order, a later job may take cores too few for an earlier one.
+pub fn schedule<F>(mut queue: Queue, launch: F) -> Result<Progress, Box<dyn std::error::Error>>
+where
+    F: Fn(&Campaign) -> Result<(), Box<dyn std::error::Error>> + Send + Sync + 'static,
+{
+    let budget = cpu::core_budget()?;
+    if let Some(job) = queue
+        .jobs
+        .iter()
+        .find(|job| cpu::cores_needed(&job.campaign.target) > budget)
+    {
+        return Err(format!(
+            "{} needs more than the {} cores available",
+            job.campaign.id(),
+            budget
+        )
+        .into());
+    }
+    queue.check_budget(budget)?;
+
+    for campaign in queue.requeue_interrupted() {
+        match set_aside(&campaign)? {
+            Some(aside) => println!(
+                "Restarting interrupted {}, its results are kept in {}",
+                campaign.id(),
+                aside
+            ),
+            None => println!("Restarting interrupted {}", campaign.id()),
+        }
+    }
+    queue.save()?;
+
+    let launch = Arc::new(launch);
+    let mut running: Vec<(usize, JoinHandle<Result<(), String>>)> = vec![];
+    loop {
+        for i in 0..queue.jobs.len() {
+            let campaign = queue.jobs[i].campaign.clone();
+            let needed = cpu::cores_needed(&campaign.target);
+            if queue.jobs[i].state != JobState::Pending
+                || cpu::reserve_cores(&[(campaign.key(), needed)]).is_err()
+            {
+                continue;
+            }
+
+            queue.set(i, JobState::Running, None);
+            queue.save()?;
+            println!("[{}] started {}", queue.progress(), campaign.id());
+
+            let launch = launch.clone();
+            let handle = std::thread::spawn(move || launch(&campaign).map_err(|e| e.to_string()));
+            running.push((i, handle));
+        }
+
+        if running.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_secs(1));
+
+        let (finished, still_running) = running
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, handle)| handle.is_finished());
+        running = still_running;
+        for (i, handle) in finished {
+            let res = handle
+                .join()
+                .unwrap_or_else(|_| Err("runner panicked".to_string()));
+            let id = queue.jobs[i].campaign.id();
+            cpu::release_cores(&queue.jobs[i].campaign.key());
+            slot::release(&queue.jobs[i].campaign.key());
+
+            match res {
+                Ok(()) => queue.set(i, JobState::Done, None),
+                Err(e) => {
+                    eprintln!("Campaign {} failed: {}", id, e);
+                    queue.set(i, JobState::Failed, Some(e));
+                }
+            }
+            queue.save()?;
+            println!("[{}] finished {}", queue.progress(), id);
+        }
+    }
+
+    Ok(queue.progress())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_progress() -> Result<(), Box<dyn std::error::Error>> {
+        let campaign = Campaign {
+            experiment: "queue".to_string(),
+            run: "20240131-093000-queue".to_string(),
+            fuzzer: "squirrel".to_string(),
+            target: "sqlite".to_string(),
+            version: "3.42.0".to_string(),
+            trial: 1,
+            duration_secs: None,
+        };
+        let campaigns = (1..=4)
+            .map(|trial| Campaign {
+                trial,
+                ..campaign.clone()
+            })
+            .collect();
+
+        let mut queue = Queue::new(&campaign.run, campaigns);
+        queue.set(0, JobState::Done, None);
+        queue.set(1, JobState::Running, None);
+        queue.set(2, JobState::Failed, Some("exit 1".to_string()));
+        assert_eq!(
+            queue.progress(),
+            Progress {
+                pending: 1,
+                running: 1,
+                done: 1,
+                failed: 1,
+            }
+        );
+
+        let json = serde_json::to_string(&queue)?;
+        let mut queue: Queue = serde_json::from_str(&json)?;
+        let interrupted = queue.requeue_interrupted();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].trial, 2);
+        assert_eq!(queue.progress().pending, 2);
+        assert_eq!(
+            queue.progress().to_string(),
+            "1 done, 1 failed, 0 running, 2 pending"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let campaign = Campaign {
+            experiment: "queue".to_string(),
+            run: "20240131-093000-queue".to_string(),
+            fuzzer: "squirrel".to_string(),
+            target: "sqlite".to_string(),
+            version: "3.42.0".to_string(),
+            trial: 1,
+            duration_secs: None,
+        };
+        let unbounded = Queue::new(&campaign.run, vec![campaign.clone(); 2]);
+        assert!(unbounded.check_budget(2).is_ok());
+        assert!(unbounded.check_budget(1).is_err());
+
+        let mut bounded = Queue::new(
+            &campaign.run,
+            vec![
+                Campaign {
+                    duration_secs: Some(3600),
+                    ..campaign.clone()
+                };
+                2
+            ],
+        );
+        assert!(bounded.check_budget(1).is_ok());
+        // Finished campaigns no longer hold cores.
+        bounded.jobs[0].campaign.duration_secs = None;
+        bounded.set(0, JobState::Done, None);
+        assert!(bounded.check_budget(1).is_ok());
+    }
+}
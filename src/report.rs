@@ -0,0 +1,174 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{campaign, source, utils};
+
+/// Columns of the monitor CSV summarized by `report`, averaged over trials
+const REPORT_COLUMNS: [&str; 8] = [
+    "cycle_num",
+    "crash_num",
+    "edge_covered",
+    "line_cov_percent",
+    "branch_cov_percent",
+    "parse_percent",
+    "exec_percent",
+    "cpu_secs",
+];
+
+/// Summarize the last sample of every campaign of `run`, averaged over its trials.
+///
+/// The table is printed and written to `runs/<run>/report.csv`, with `-` for
+/// the metrics none of the trials reports.
+pub fn report(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // (fuzzer, target, version) -> final samples of each trial
+    let mut groups: BTreeMap<(String, String, String), Vec<Vec<f64>>> = BTreeMap::new();
+    for c in campaign::load(run)? {
+        let Some(sample) = last_sample(&c.output_csv(), &REPORT_COLUMNS)? else {
+            continue;
+        };
+        groups
+            .entry((c.fuzzer.clone(), c.target.clone(), c.version.clone()))
+            .or_default()
+            .push(sample);
+    }
+
+    let report_csv = format!("{}/report.csv", campaign::run_dir(run));
+    let mut wtr = csv::Writer::from_path(&report_csv)?;
+    let mut header = vec!["fuzzer", "target", "version", "trials"];
+    header.extend(REPORT_COLUMNS);
+    wtr.write_record(&header)?;
+    println!("{}", header.join("\t"));
+
+    for ((fuzzer, target, version), samples) in groups {
+        let mut row = vec![fuzzer, target, version, samples.len().to_string()];
+        for i in 0..REPORT_COLUMNS.len() {
+            match mean(samples.iter().map(|s| s[i])) {
+                Some(mean) => row.push(format!("{:.2}", mean)),
+                None => row.push("-".to_string()),
+            }
+        }
+        wtr.write_record(&row)?;
+        println!("{}", row.join("\t"));
+    }
+    wtr.flush()?;
+
+    Ok(())
+}
+
+/// Mean of the known values, skipping the -1 of trials without the metric
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let known = values.filter(|v| *v >= 0.0).collect::<Vec<_>>();
+    if known.is_empty() {
+        return None;
+    }
+
+    Some(known.iter().sum::<f64>() / known.len() as f64)
+}
+
+/// Values of `columns` in the last row of a monitor CSV, -1 where missing
+pub(crate) fn last_sample(
+    csv_file: &str,
+    columns: &[&str],
+) -> Result<Option<Vec<f64>>, Box<dyn std::error::Error>> {
+    Ok(last_row(csv_file, columns)?.map(|row| {
+        row.iter()
+            .map(|v| v.as_deref().and_then(|v| v.parse().ok()).unwrap_or(-1.0))
+            .collect()
+    }))
+}
+
+/// Raw values of `columns` in the last row of a monitor CSV, absent where missing
+pub(crate) fn last_row(
+    csv_file: &str,
+    columns: &[&str],
+) -> Result<Option<Vec<Option<String>>>, Box<dyn std::error::Error>> {
+    if !Path::new(csv_file).exists() {
+        return Ok(None);
+    }
+
+    let mut rdr = csv::Reader::from_path(csv_file)?;
+    let headers = rdr.headers()?.clone();
+    let Some(row) = rdr.records().filter_map(|r| r.ok()).last() else {
+        return Ok(None);
+    };
+
+    let values = columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|h| h == *column)
+                .and_then(|i| row.get(i))
+                .map(|v| v.to_string())
+        })
+        .collect();
+
+    Ok(Some(values))
+}
+
+/// Collect the crashing inputs of every campaign of `run`, deduplicated by content.
+///
+/// Unique crashes are copied to `runs/<run>/crashes/<campaign>/<sha256>`.
+pub fn triage(run: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let crashes_dir = format!("{}/crashes", campaign::run_dir(run));
+
+    println!("campaign\tcrashes\tunique");
+    for c in campaign::load(run)? {
+        let mut total = 0;
+        let mut unique = BTreeMap::new();
+        // AFL instances keep their crashes in `<output>/<instance>/crashes`.
+        for instance in fs::read_dir(format!("{}/test", c.dir()))
+            .into_iter()
+            .flatten()
+        {
+            let dir = instance?.path().join("crashes");
+            for entry in fs::read_dir(&dir).into_iter().flatten() {
+                let path = entry?.path();
+                if !path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("id:"))
+                {
+                    continue;
+                }
+                total += 1;
+                let path = path.display().to_string();
+                unique.insert(source::sha256_file(&path)?, path);
+            }
+        }
+
+        if !unique.is_empty() {
+            let dest_dir = format!("{}/{}", crashes_dir, c.id());
+            utils::create_dir(&dest_dir)?;
+            for (digest, path) in &unique {
+                fs::copy(path, format!("{}/{}", dest_dir, digest))?;
+            }
+        }
+        println!("{}\t{}\t{}", c.id(), total, unique.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_sample() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = crate::utils::test_dir("report");
+        let csv_file = dir.join("monitor.csv");
+        fs::write(
+            &csv_file,
+            "name,cycle_num,crash_num,edge_covered\na,10,0,5\na,20,1,7\n",
+        )?;
+
+        let sample = last_sample(&csv_file.display().to_string(), &REPORT_COLUMNS)?.unwrap();
+        assert_eq!(sample, vec![20.0, 1.0, 7.0, -1.0, -1.0, -1.0, -1.0, -1.0]);
+        assert!(last_sample("/nonexistent.csv", &REPORT_COLUMNS)?.is_none());
+
+        assert_eq!(mean([20.0, -1.0, 40.0].into_iter()), Some(30.0));
+        assert_eq!(mean([-1.0, -1.0].into_iter()), None);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
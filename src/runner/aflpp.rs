@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use crate::campaign::Campaign;
+
+use super::{afl_fuzz, campaign_envs, db, squirrel::db_driver, Runner};
+
+/// Plain AFL++ on the same checkout and driver as Squirrel, without its custom mutator
+pub struct AflppRunner;
+
+impl Runner for AflppRunner {
+    fn run_pgsql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let pg_envs = baseline_envs(campaign_envs(campaign)?);
+
+        db::init_pgsql(&pg_envs, &campaign.log_target())?;
+        afl_fuzz(&pg_envs, campaign, &db_driver())
+    }
+
+    fn run_mysql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let my_envs = baseline_envs(campaign_envs(campaign)?);
+
+        db::init_mysql(&my_envs, &campaign.log_target())?;
+        afl_fuzz(&my_envs, campaign, &db_driver())
+    }
+
+    fn run_mariadb(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let ma_envs = baseline_envs(campaign_envs(campaign)?);
+
+        db::init_mariadb(&ma_envs, &campaign.log_target())?;
+        afl_fuzz(&ma_envs, campaign, &db_driver())
+    }
+
+    fn run_sqlite(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let sl_envs = baseline_envs(campaign_envs(campaign)?);
+
+        let harness = format!("{}/bin/ossfuzz", sl_envs["SQLITE_INSTALL_PATH"]);
+        afl_fuzz(&sl_envs, campaign, &harness)
+    }
+}
+
+/// Squirrel envs with its custom mutator left out
+fn baseline_envs(mut envs: HashMap<&'static str, String>) -> HashMap<&'static str, String> {
+    envs.remove("AFL_CUSTOM_MUTATOR_ONLY");
+    envs.remove("AFL_CUSTOM_MUTATOR_LIBRARY");
+    envs
+}
@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::constants::{CGROUP_CONFIG, CGROUP_ROOT};
+
+/// Limits applied to every campaign cgroup, read from `config/cgroup.toml`
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CgroupConfig {
+    /// Value of `memory.max`, e.g. `16G`
+    pub memory_max: Option<String>,
+
+    /// Value of `cpu.max`, e.g. `200000 100000`
+    pub cpu_max: Option<String>,
+}
+
+static CONFIG: Lazy<CgroupConfig> =
+    Lazy::new(|| CgroupConfig::load(CGROUP_CONFIG.as_str()).expect("Invalid cgroup config"));
+
+impl CgroupConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+/// Whether any limit is configured, making a missing cgroup an error
+pub fn has_limits() -> bool {
+    CONFIG.memory_max.is_some() || CONFIG.cpu_max.is_some()
+}
+
+/// cgroup of a campaign, keyed by `Campaign::key`
+pub fn campaign_cgroup(campaign: &str) -> String {
+    format!("{}/{}", CGROUP_ROOT.as_str(), campaign)
+}
+
+/// Create the cgroup of `campaign` with the configured limits, returning its directory.
+pub fn create(campaign: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let root = CGROUP_ROOT.as_str();
+    fs::create_dir_all(root)?;
+    // Controllers must be enabled on the parent for the children to get their files.
+    fs::write(format!("{}/cgroup.subtree_control", root), "+cpu +memory")?;
+
+    let dir = campaign_cgroup(campaign);
+    fs::create_dir_all(&dir)?;
+    if let Some(memory_max) = &CONFIG.memory_max {
+        fs::write(format!("{}/memory.max", dir), memory_max)?;
+    }
+    if let Some(cpu_max) = &CONFIG.cpu_max {
+        fs::write(format!("{}/cpu.max", dir), cpu_max)?;
+    }
+
+    Ok(dir)
+}
+
+/// Shell prefix moving the rest of a command line, children included, into `cgroup`.
+pub fn join(cgroup: &str) -> [String; 5] {
+    [
+        "echo".to_string(),
+        "$$".to_string(),
+        ">".to_string(),
+        format!("{}/cgroup.procs", cgroup),
+        "&&".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cgroup_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config: CgroupConfig = toml::from_str("memory_max = \"16G\"")?;
+        assert_eq!(config.memory_max.as_deref(), Some("16G"));
+        assert!(config.cpu_max.is_none());
+        assert!(toml::from_str::<CgroupConfig>("memory = \"16G\"").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+/// Cores reserved for each campaign of this process, keyed by `Campaign::key`
+static RESERVED: Lazy<Mutex<HashMap<String, Vec<usize>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cores one campaign against `target` needs: its fuzzer, plus the server if there is one
+pub fn cores_needed(target: &str) -> usize {
+    match target {
+        "postgresql" | "mysql" | "mariadb" => 2,
+        _ => 1,
+    }
+}
+
+/// Reserve dedicated cores for all `campaigns` at once, refusing to oversubscribe the machine.
+pub fn reserve_cores(campaigns: &[(String, usize)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reserved = RESERVED.lock().unwrap();
+    let mut free = available_cores()?
+        .into_iter()
+        .filter(|core| !reserved.values().flatten().any(|c| c == core))
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    let campaigns = campaigns
+        .iter()
+        .filter(|(campaign, _)| !reserved.contains_key(campaign))
+        .collect::<Vec<_>>();
+    let needed: usize = campaigns.iter().map(|(_, count)| count).sum();
+    if needed > free.len() {
+        return Err(format!(
+            "Campaigns need {} dedicated cores but only {} are free, \
+             run fewer of them or widen $FUZZER_COMPARE_CORES",
+            needed,
+            free.len()
+        )
+        .into());
+    }
+
+    for (campaign, count) in campaigns {
+        reserved.insert(campaign.clone(), free.by_ref().take(*count).collect());
+    }
+
+    Ok(())
+}
+
+/// Cores of `campaign`, reserving `count` of them if the launcher has not already.
+pub fn campaign_cores(
+    campaign: &str,
+    count: usize,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    reserve_cores(&[(campaign.to_string(), count)])?;
+
+    Ok(RESERVED.lock().unwrap()[campaign].clone())
+}
+
+/// Hand the cores of a finished campaign back to the budget.
+pub fn release_cores(campaign: &str) {
+    RESERVED.lock().unwrap().remove(campaign);
+}
+
+/// Number of cores campaigns may use in total
+pub fn core_budget() -> Result<usize, Box<dyn std::error::Error>> {
+    Ok(available_cores()?.len())
+}
+
+/// Cores campaigns may use, `$FUZZER_COMPARE_CORES` if set, else the affinity of this process
+fn available_cores() -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    if let Ok(list) = std::env::var("FUZZER_COMPARE_CORES") {
+        return parse_cpu_list(&list);
+    }
+
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    match status
+        .lines()
+        .find_map(|l| l.strip_prefix("Cpus_allowed_list:"))
+    {
+        Some(list) => parse_cpu_list(list),
+        None => Ok((0..std::thread::available_parallelism()?.get()).collect()),
+    }
+}
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    let mut cores = vec![];
+    for item in list.trim().split(',').filter(|i| !i.is_empty()) {
+        match item.split_once('-') {
+            Some((first, last)) => {
+                cores.extend(first.trim().parse::<usize>()?..=last.trim().parse()?)
+            }
+            None => cores.push(item.trim().parse()?),
+        }
+    }
+
+    Ok(cores)
+}
+
+/// Shell prefix pinning the rest of a command line, children included, to `cores`.
+pub fn taskset(cores: &str) -> [String; 6] {
+    [
+        "taskset".to_string(),
+        "-pc".to_string(),
+        cores.to_string(),
+        "$$".to_string(),
+        ">/dev/null".to_string(),
+        "&&".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n")?,
+            vec![0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpu_list("5")?, vec![5]);
+        assert!(parse_cpu_list("a-b").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserve_cores() -> Result<(), Box<dyn std::error::Error>> {
+        let total = available_cores()?.len();
+
+        let a = campaign_cores("test-sl-a", 1)?;
+        assert_eq!(a, campaign_cores("test-sl-a", 1)?);
+        assert!(reserve_cores(&[("test-oversubscribed".to_string(), total)]).is_err());
+        assert!(!RESERVED.lock().unwrap().contains_key("test-oversubscribed"));
+
+        release_cores("test-sl-a");
+        assert!(!RESERVED.lock().unwrap().contains_key("test-sl-a"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::{
+    campaign::Campaign,
+    fuzzer::{self, FuzzerConfig},
+    manifest::Manifest,
+    utils::{self, ShellCommand},
+};
+
+use super::{base_envs, confine, db, squirrel_seeds};
+
+/// Runs a fuzzer defined in `config/fuzzers.toml` from its launch command template
+pub struct CustomRunner;
+
+impl CustomRunner {
+    pub fn run(
+        config: &FuzzerConfig,
+        campaign: &Campaign,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (name, target) = (campaign.fuzzer.as_str(), campaign.target.as_str());
+        let log_target = campaign.log_target();
+        let envs = base_envs(campaign)?;
+
+        match target {
+            "postgresql" => db::init_pgsql(&envs, &log_target)?,
+            "mysql" => db::init_mysql(&envs, &log_target)?,
+            "mariadb" => db::init_mariadb(&envs, &log_target)?,
+            _ => {}
+        }
+        if config.start_server {
+            db::start_pgsql(&envs, &log_target)?;
+        }
+
+        let fuzzer_dir = fuzzer::fuzzer_dir(name);
+        let input_dir = match &config.input_dir {
+            Some(input_dir) => input_dir.clone(),
+            None => squirrel_seeds(target),
+        };
+        let target_bin = campaign.target_bin();
+        let launch_cmd = fuzzer::render(
+            &config.launch,
+            &[
+                ("input_dir", &input_dir),
+                ("output_dir", &envs["TEST_PATH"]),
+                ("target_bin", &target_bin),
+                ("port", envs.get("DB_PORT").map_or("", |p| p.as_str())),
+            ],
+        );
+
+        let mut launch_envs: HashMap<String, String> = envs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        launch_envs.extend(config.env.clone());
+
+        let mut run_cmd = confine(&envs, Some("FUZZER_CORE"));
+        match campaign.duration() {
+            Some(duration) => run_cmd.extend([
+                "timeout".to_string(),
+                "--signal=INT".to_string(),
+                duration.as_secs().to_string(),
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                quote(&launch_cmd),
+            ]),
+            None => run_cmd.push(launch_cmd),
+        }
+        Manifest::new(
+            campaign,
+            config.source(name),
+            &launch_envs,
+            &run_cmd,
+            Some(&input_dir),
+        )
+        .create()?;
+        let res = ShellCommand::new()
+            .args(&run_cmd)
+            .current_dir(&fuzzer_dir)
+            .envs(&launch_envs)
+            .log_to(utils::log_file("run", &log_target, "launch"))
+            .run();
+
+        if config.start_server {
+            db::stop_pgsql(&envs, &log_target)?;
+        }
+
+        res
+    }
+}
+
+/// Single-quote `s` for the shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
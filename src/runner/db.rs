@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use crate::{
+    constants::RUNS_DIR,
+    utils::{self, ShellCommand},
+};
+
+use super::confine;
+
+/// Initialize the PostgreSQL data directory of a campaign, unless a previous launch did.
+pub fn init_pgsql(
+    envs: &HashMap<&'static str, String>,
+    log_target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if initialized(envs, "PG_VERSION") {
+        return Ok(());
+    }
+    utils::create_dir(&envs["DATA_DIR"])?;
+
+    let init_cmd = [
+        format!("{}/bin/initdb", envs["PGSQL_INSTALL_PATH"]),
+        "-D".to_string(),
+        "$DATA_DIR".to_string(),
+    ];
+    ShellCommand::new()
+        .args(&init_cmd)
+        .current_dir(RUNS_DIR.as_str())
+        .envs(envs)
+        .log_to(utils::log_file("run", log_target, "initdb"))
+        .run()
+}
+
+/// Start the PostgreSQL server of a campaign on its own port, core and cgroup,
+/// and wait until it accepts connections.
+pub fn start_pgsql(
+    envs: &HashMap<&'static str, String>,
+    log_target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut start_cmd = confine(envs, Some("SERVER_CORE"));
+    start_cmd.extend([
+        format!("{}/bin/pg_ctl", envs["PGSQL_INSTALL_PATH"]),
+        "-D".to_string(),
+        "$DATA_DIR".to_string(),
+        "-l".to_string(),
+        "$TEST_PATH/postgres.log".to_string(),
+        "-o".to_string(),
+        format!("'-p {} -k {}'", envs["DB_PORT"], envs["DB_SOCKET_DIR"]),
+        "-w".to_string(),
+        "start".to_string(),
+    ]);
+    ShellCommand::new()
+        .args(&start_cmd)
+        .current_dir(RUNS_DIR.as_str())
+        .envs(envs)
+        .log_to(utils::log_file("run", log_target, "pg-start"))
+        .run()
+}
+
+/// Stop the PostgreSQL server of a campaign, flushing the coverage profiles of its processes.
+pub fn stop_pgsql(
+    envs: &HashMap<&'static str, String>,
+    log_target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stop_cmd = [
+        format!("{}/bin/pg_ctl", envs["PGSQL_INSTALL_PATH"]),
+        "-D".to_string(),
+        "$DATA_DIR".to_string(),
+        "-m".to_string(),
+        "fast".to_string(),
+        "-w".to_string(),
+        "stop".to_string(),
+    ];
+    ShellCommand::new()
+        .args(&stop_cmd)
+        .current_dir(RUNS_DIR.as_str())
+        .envs(envs)
+        .log_to(utils::log_file("run", log_target, "pg-stop"))
+        .run()
+}
+
+/// Whether the PostgreSQL server of a campaign is running
+pub fn pgsql_running(envs: &HashMap<&'static str, String>) -> bool {
+    std::process::Command::new(format!("{}/bin/pg_ctl", envs["PGSQL_INSTALL_PATH"]))
+        .args(["-D", &envs["DATA_DIR"], "status"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Initialize the MySQL data directory of a campaign, unless a previous launch did.
+pub fn init_mysql(
+    envs: &HashMap<&'static str, String>,
+    log_target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if initialized(envs, "mysql") {
+        return Ok(());
+    }
+    utils::create_dir(&envs["DATA_DIR"])?;
+
+    let init_cmd = [
+        format!("{}/bin/mysqld", envs["MYSQL_INSTALL_PATH"]),
+        "--initialize-insecure".to_string(),
+        format!("--basedir={}", envs["MYSQL_INSTALL_PATH"]),
+        "--datadir=$DATA_DIR".to_string(),
+    ];
+    ShellCommand::new()
+        .args(&init_cmd)
+        .current_dir(RUNS_DIR.as_str())
+        .envs(envs)
+        .log_to(utils::log_file("run", log_target, "initdb"))
+        .run()
+}
+
+/// Initialize the MariaDB data directory of a campaign, unless a previous launch did.
+pub fn init_mariadb(
+    envs: &HashMap<&'static str, String>,
+    log_target: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if initialized(envs, "mysql") {
+        return Ok(());
+    }
+    utils::create_dir(&envs["DATA_DIR"])?;
+
+    let init_cmd = [
+        format!(
+            "{}/scripts/mariadb-install-db",
+            envs["MARIADB_INSTALL_PATH"]
+        ),
+        format!("--basedir={}", envs["MARIADB_INSTALL_PATH"]),
+        "--datadir=$DATA_DIR".to_string(),
+    ];
+    ShellCommand::new()
+        .args(&init_cmd)
+        .current_dir(RUNS_DIR.as_str())
+        .envs(envs)
+        .log_to(utils::log_file("run", log_target, "initdb"))
+        .run()
+}
+
+/// Whether the data directory holds `marker`, left by a finished initialization
+fn initialized(envs: &HashMap<&'static str, String>, marker: &str) -> bool {
+    std::path::Path::new(&format!("{}/{}", envs["DATA_DIR"], marker)).exists()
+}
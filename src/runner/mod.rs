@@ -1,9 +1,311 @@
+pub use aflpp::AflppRunner;
+pub use custom::CustomRunner;
+pub use sqlsmith::SqlsmithRunner;
 pub use squirrel::SquirrelRunner;
 
+mod aflpp;
+pub(crate) mod cgroup;
+pub(crate) mod cpu;
+mod custom;
+mod db;
+pub(crate) mod slot;
+mod sqlsmith;
 mod squirrel;
 
+use std::{collections::HashMap, fs};
+
+use crate::{
+    campaign::Campaign,
+    constants::{FUZZERS_DIR, SQUIRREL_ENVS_TABLE},
+    manifest::{self, Manifest},
+    target,
+    utils::{self, ShellCommand},
+};
+
+use slot::Slot;
+
 pub trait Runner {
-    fn run_pgsql() -> Result<(), Box<dyn std::error::Error>>;
-    fn run_mysql() -> Result<(), Box<dyn std::error::Error>>;
-    fn run_mariadb() -> Result<(), Box<dyn std::error::Error>>;
+    fn run_pgsql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>>;
+    fn run_mysql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>>;
+    fn run_mariadb(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>>;
+    fn run_sqlite(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Run `campaign` with the method of its target.
+    fn run(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        match campaign.target.as_str() {
+            "postgresql" => Self::run_pgsql(campaign),
+            "mysql" => Self::run_mysql(campaign),
+            "mariadb" => Self::run_mariadb(campaign),
+            "sqlite" => Self::run_sqlite(campaign),
+            target => Err(format!("Unknown target {}", target).into()),
+        }
+    }
+}
+
+/// Env var pointing at the install prefix of `target`
+fn install_var(target: &str) -> &'static str {
+    match target {
+        "postgresql" => "PGSQL_INSTALL_PATH",
+        "mysql" => "MYSQL_INSTALL_PATH",
+        "mariadb" => "MARIADB_INSTALL_PATH",
+        _ => "SQLITE_INSTALL_PATH",
+    }
+}
+
+/// Squirrel envs of `campaign`
+fn campaign_envs(
+    campaign: &Campaign,
+) -> Result<HashMap<&'static str, String>, Box<dyn std::error::Error>> {
+    let db = campaign.target.as_str();
+    let mut envs = SQUIRREL_ENVS_TABLE.get(db).unwrap().to_owned();
+    envs.extend(base_envs(campaign)?);
+
+    // Squirrel reads the server address from its config, so every campaign gets its own copy.
+    let template = fs::read_to_string(&envs["SQUIRREL_CONFIG"])?;
+    let config = squirrel_config(db, &template, &envs);
+    let config_file = format!("{}/squirrel_config.yml", campaign.dir());
+    fs::write(&config_file, config)?;
+    envs.insert("SQUIRREL_CONFIG", config_file);
+
+    Ok(envs)
+}
+
+/// Squirrel config of `db` with the port, socket, data directory and server core of the campaign
+fn squirrel_config(db: &str, template: &str, envs: &HashMap<&'static str, String>) -> String {
+    let (port, data_dir) = match (envs.get("DB_PORT"), envs.get("DATA_DIR")) {
+        (Some(port), Some(data_dir)) => (port, data_dir),
+        _ => return template.to_string(),
+    };
+    // The driver starts the server from inside afl-fuzz, move it off the fuzzer's core.
+    let pin = envs
+        .get("SERVER_CORE")
+        .map(|core| format!("taskset -c {} ", core))
+        .unwrap_or_default();
+
+    let overrides = match db {
+        "postgresql" => vec![
+            ("port", port.clone()),
+            (
+                "startup_cmd",
+                format!(
+                    "\"{}$PGSQL_INSTALL_PATH/bin/postgres -D {} -p {} -k {} &\"",
+                    pin, data_dir, port, envs["DB_SOCKET_DIR"]
+                ),
+            ),
+        ],
+        "mysql" => vec![
+            ("sock_path", format!("\"{}\"", envs["DB_SOCKET"])),
+            (
+                "startup_cmd",
+                format!(
+                    "\"{}$MYSQL_INSTALL_PATH/bin/mysqld --port={} --socket={} --basedir=$MYSQL_INSTALL_PATH --datadir={} &\"",
+                    pin, port, envs["DB_SOCKET"], data_dir
+                ),
+            ),
+        ],
+        "mariadb" => vec![
+            ("sock_path", format!("\"{}\"", envs["DB_SOCKET"])),
+            (
+                "startup_cmd",
+                format!(
+                    "\"{}$MARIADB_INSTALL_PATH/bin/mariadbd --skip-grant-tables --port={} --socket={} --basedir=$MARIADB_INSTALL_PATH --datadir={} &\"",
+                    pin, port, envs["DB_SOCKET"], data_dir
+                ),
+            ),
+        ],
+        _ => vec![],
+    };
+
+    set_yaml_keys(template, &overrides)
+}
+
+/// Replace the top-level `key: value` lines of a flat YAML document, appending missing keys.
+fn set_yaml_keys(template: &str, overrides: &[(&str, String)]) -> String {
+    let mut missing = overrides.iter().collect::<Vec<_>>();
+    let mut lines = vec![];
+    for line in template.lines() {
+        let key = line.split_once(':').map(|(k, _)| k);
+        match missing.iter().position(|(k, _)| Some(*k) == key) {
+            Some(i) => {
+                let (k, v) = missing.remove(i);
+                lines.push(format!("{}: {}", k, v));
+            }
+            // Drop later duplicates of a replaced key.
+            None if overrides.iter().any(|(k, _)| Some(*k) == key) => {}
+            None => lines.push(line.to_string()),
+        }
+    }
+    for (k, v) in missing {
+        lines.push(format!("{}: {}", k, v));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Test, install and coverage profile paths of `campaign`, together with
+/// the port, socket, data directory, cores and cgroup of its server.
+///
+/// Creates the test directory, and records the campaign and its resources in its directory.
+fn base_envs(
+    campaign: &Campaign,
+) -> Result<HashMap<&'static str, String>, Box<dyn std::error::Error>> {
+    let (db, version) = (campaign.target.as_str(), campaign.version.as_str());
+    let install_dir = target::install_dir(db, version);
+    if !std::path::Path::new(&install_dir).exists() {
+        return Err(format!(
+            "{} {} is not installed, add it to the targets config and run setup",
+            db, version
+        )
+        .into());
+    }
+
+    let campaign_dir = campaign.dir();
+    let key = campaign.key();
+    let mut envs = HashMap::new();
+    envs.insert("TEST_PATH", format!("{}/test", campaign_dir));
+    // Fuzzer output, server logs and runner stats all go here, whatever the runner.
+    utils::create_dir(&envs["TEST_PATH"])?;
+    envs.insert(install_var(db), install_dir);
+
+    let slot = Slot::allocate(db, &key, &campaign_dir)?;
+    if let Some(slot) = &slot {
+        envs.insert("DB_PORT", slot.port.to_string());
+        if db != "postgresql" {
+            envs.insert("DB_SOCKET", slot.mysql_socket());
+        }
+        envs.insert("DB_SOCKET_DIR", slot.socket_dir.clone());
+        envs.insert("DATA_DIR", slot.data_dir.clone());
+    }
+
+    let cores = cpu::campaign_cores(&key, cpu::cores_needed(db))?;
+    envs.insert("FUZZER_CORE", cores[0].to_string());
+    if let Some(core) = cores.get(1) {
+        envs.insert("SERVER_CORE", core.to_string());
+    }
+
+    let cgroup = match cgroup::create(&key) {
+        Ok(cgroup) => Some(cgroup),
+        Err(e) if !cgroup::has_limits() => {
+            eprintln!("Running {} without cgroup accounting: {}", key, e);
+            None
+        }
+        Err(e) => return Err(format!("Cannot create the cgroup of {}: {}", key, e).into()),
+    };
+    if let Some(cgroup) = &cgroup {
+        envs.insert("CGROUP", cgroup.clone());
+    }
+
+    envs.insert(
+        "LLVM_PROFILE_FILE",
+        format!("{}/prof/{}-%p-%m.profraw", campaign_dir, campaign.name()),
+    );
+
+    // A restarted campaign keeps the record of its first launch.
+    if campaign.started().is_none() {
+        campaign.save()?;
+    }
+    let resources = serde_json::json!({
+        "fuzzer_core": cores[0],
+        "server_core": cores.get(1),
+        "port": slot.as_ref().map(|s| s.port),
+        "cgroup": cgroup,
+    });
+    fs::write(
+        format!("{}/resources.json", campaign_dir),
+        serde_json::to_string_pretty(&resources)?,
+    )?;
+
+    Ok(envs)
+}
+
+/// Squirrel's seed directory for `target`, shared by the fuzzers without their own seeds
+fn squirrel_seeds(target: &str) -> String {
+    let input = match target {
+        "postgresql" => "pqsql_input",
+        "mysql" | "mariadb" => "mysql_input",
+        _ => "input",
+    };
+
+    format!("{}/squirrel/data/fuzz_root/{}", FUZZERS_DIR.as_str(), input)
+}
+
+/// Shell prefix moving the rest of a command line into the campaign cgroup,
+/// and onto the core named by `core_var` if any.
+fn confine(envs: &HashMap<&'static str, String>, core_var: Option<&str>) -> Vec<String> {
+    let mut prefix = vec![];
+    if let Some(cgroup) = envs.get("CGROUP") {
+        prefix.extend(cgroup::join(cgroup));
+    }
+    if let Some(core) = core_var.and_then(|var| envs.get(var)) {
+        prefix.extend(cpu::taskset(core));
+    }
+
+    prefix
+}
+
+/// Run the AFL++ shipped with Squirrel on `target_bin` from Squirrel's seeds,
+/// until it exits or the time budget of `campaign` runs out.
+fn afl_fuzz(
+    envs: &HashMap<&'static str, String>,
+    campaign: &Campaign,
+    target_bin: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // afl-fuzz binds itself to its core, so only the cgroup is joined here.
+    let seeds = squirrel_seeds(&campaign.target);
+    let mut run_cmd = confine(envs, None);
+    run_cmd.extend([
+        format!("{}/squirrel/AFLplusplus/afl-fuzz", FUZZERS_DIR.as_str()),
+        "-i".to_string(),
+        seeds.clone(),
+        "-o".to_string(),
+        "$TEST_PATH".to_string(),
+        "-t".to_string(),
+        "120000".to_string(),
+        "-b".to_string(),
+        "$FUZZER_CORE".to_string(),
+        "-S".to_string(),
+        "1".to_string(),
+    ]);
+    if let Some(duration) = campaign.duration() {
+        run_cmd.extend(["-V".to_string(), duration.as_secs().to_string()]);
+    }
+    run_cmd.extend(["--".to_string(), target_bin.to_string()]);
+
+    let fuzzer_source = manifest::builtin_source(&campaign.fuzzer);
+    Manifest::new(campaign, fuzzer_source, envs, &run_cmd, Some(&seeds)).create()?;
+    ShellCommand::new()
+        .args(&run_cmd)
+        .current_dir(campaign.dir())
+        .envs(envs)
+        // A restarted campaign continues from the queue of its first launch.
+        .envs([("AFL_AUTORESUME", "1")])
+        .log_to(utils::log_file("run", &campaign.log_target(), "afl-fuzz"))
+        .run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squirrel_config() {
+        let template = "db: mysql\nsock_path: \"/tmp/mysql.sock\"\nstartup_cmd: \"mysqld &\"\n";
+        let envs = HashMap::from([
+            ("DB_PORT", "13306".to_string()),
+            ("DB_SOCKET", "/tmp/fc/my/mysql.sock".to_string()),
+            ("DB_SOCKET_DIR", "/tmp/fc/my".to_string()),
+            ("DATA_DIR", "/test/my/data".to_string()),
+            ("SERVER_CORE", "3".to_string()),
+        ]);
+
+        let config = squirrel_config("mysql", template, &envs);
+        assert!(config.contains("db: mysql\n"));
+        assert!(config.contains("sock_path: \"/tmp/fc/my/mysql.sock\"\n"));
+        assert!(config.contains("\"taskset -c 3 $MYSQL_INSTALL_PATH/bin/mysqld --port=13306 --socket=/tmp/fc/my/mysql.sock"));
+        assert!(config.contains("--datadir=/test/my/data &"));
+        assert_eq!(config.matches("startup_cmd").count(), 1);
+
+        let config = squirrel_config("postgresql", "db: postgresql\n", &envs);
+        assert!(config.contains("port: 13306\n"));
+    }
 }
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    net::TcpListener,
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    campaign,
+    constants::{MARIADB_BASE_PORT, MYSQL_BASE_PORT, PGSQL_BASE_PORT, SOCKET_DIR},
+    utils,
+};
+
+/// Port of the server of each campaign of this process, keyed by `Campaign::key`
+static TAKEN_PORTS: Lazy<Mutex<HashMap<String, u16>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How far above its base port a server's port is searched for
+const PORT_RANGE: u16 = 1000;
+
+/// How long a restarted campaign waits for its killed server to let go of its socket
+const SERVER_EXIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Port, unix socket and data directory owned by a single campaign server
+pub struct Slot {
+    pub port: u16,
+    pub socket_dir: String,
+    pub data_dir: String,
+}
+
+impl Slot {
+    /// Reserve a free port and a socket directory for the server of `campaign`.
+    ///
+    /// A restarted campaign gets its port back. Returns `None` for targets
+    /// without a server, e.g. SQLite.
+    pub fn allocate(
+        db: &str,
+        campaign: &str,
+        campaign_dir: &str,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        Self::allocate_in(SOCKET_DIR.as_str(), db, campaign, campaign_dir)
+    }
+
+    /// `allocate` with the sockets and port reservations kept under `socket_root`
+    fn allocate_in(
+        socket_root: &str,
+        db: &str,
+        campaign: &str,
+        campaign_dir: &str,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let base_port = match db {
+            "postgresql" => PGSQL_BASE_PORT,
+            "mysql" => MYSQL_BASE_PORT,
+            "mariadb" => MARIADB_BASE_PORT,
+            _ => return Ok(None),
+        };
+
+        let socket_dir = format!("{}/{}", socket_root, campaign);
+        let reused = TAKEN_PORTS.lock().unwrap().get(campaign).copied();
+        let port = match reused {
+            Some(port) => {
+                wait_for_exit(campaign, &socket_dir, port)?;
+                port
+            }
+            None => {
+                if let Some(socket) = listening_socket(&socket_dir) {
+                    return Err(format!(
+                        "A server of {} is still listening on {}, stop it first",
+                        campaign, socket
+                    )
+                    .into());
+                }
+
+                let mut taken = TAKEN_PORTS.lock().unwrap();
+                let port = (base_port..base_port + PORT_RANGE)
+                    .find(|port| {
+                        !taken.values().any(|p| p == port)
+                            && !port_in_use(*port)
+                            && reserve_port(socket_root, *port)
+                    })
+                    .ok_or_else(|| {
+                        format!(
+                            "No free port for {} within {}..{}",
+                            campaign,
+                            base_port,
+                            base_port + PORT_RANGE
+                        )
+                    })?;
+                taken.insert(campaign.to_string(), port);
+                port
+            }
+        };
+        utils::create_dir(&socket_dir)?;
+
+        Ok(Some(Self {
+            port,
+            socket_dir,
+            data_dir: format!("{}/data", campaign_dir),
+        }))
+    }
+
+    /// Socket file MySQL and MariaDB listen on
+    pub fn mysql_socket(&self) -> String {
+        format!("{}/mysql.sock", self.socket_dir)
+    }
+}
+
+/// Hand the port of a finished campaign back, to this and every other process.
+pub fn release(campaign: &str) {
+    release_in(SOCKET_DIR.as_str(), campaign);
+}
+
+fn release_in(socket_root: &str, campaign: &str) {
+    if let Some(port) = TAKEN_PORTS.lock().unwrap().remove(campaign) {
+        let _ = fs::remove_file(reservation_file(socket_root, port));
+    }
+}
+
+/// Whether something already listens on `port` of the loopback interface
+pub fn port_in_use(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_err()
+}
+
+/// File through which processes of all runs agree on who owns `port`
+fn reservation_file(socket_root: &str, port: u16) -> String {
+    format!("{}/ports/{}", socket_root, port)
+}
+
+/// Claim `port` for this process, unless a live process claimed it before.
+///
+/// A server only binds its port once it started, so two processes looking for
+/// a free port at the same time would otherwise pick the same one.
+fn reserve_port(socket_root: &str, port: u16) -> bool {
+    let file = reservation_file(socket_root, port);
+    if let Some(parent) = Path::new(&file).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    for _ in 0..2 {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&file)
+        {
+            Ok(mut f) => return write!(f, "{}", std::process::id()).is_ok(),
+            Err(_) => {
+                // A reservation left behind by a process that died is free again.
+                let owner = fs::read_to_string(&file).ok();
+                match owner.and_then(|pid| pid.trim().parse().ok()) {
+                    Some(pid) if campaign::pid_alive(pid) => return false,
+                    _ => {
+                        let _ = fs::remove_file(&file);
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Wait until the killed server of a restarted campaign released its socket and port.
+fn wait_for_exit(
+    campaign: &str,
+    socket_dir: &str,
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + SERVER_EXIT_TIMEOUT;
+    while listening_socket(socket_dir).is_some() || port_in_use(port) {
+        if Instant::now() > deadline {
+            return Err(format!(
+                "The previous server of {} still holds port {} after {} seconds",
+                campaign,
+                port,
+                SERVER_EXIT_TIMEOUT.as_secs()
+            )
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+
+    Ok(())
+}
+
+/// First socket of `dir` that still accepts connections
+fn listening_socket(dir: &str) -> Option<String> {
+    fs::read_dir(Path::new(dir))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| UnixStream::connect(path).is_ok())
+        .map(|path| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_skips_used_ports() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = utils::test_dir("slots");
+        let root = dir.display().to_string();
+        let listener = TcpListener::bind(("127.0.0.1", MARIADB_BASE_PORT));
+
+        let a = Slot::allocate_in(&root, "mariadb", "test-ma-a", "/tmp/test-ma-a")?.unwrap();
+        let b = Slot::allocate_in(&root, "mariadb", "test-ma-b", "/tmp/test-ma-b")?.unwrap();
+        assert_ne!(a.port, b.port);
+        if listener.is_ok() {
+            assert_ne!(a.port, MARIADB_BASE_PORT);
+        }
+        assert_eq!(a.data_dir, "/tmp/test-ma-a/data");
+        assert_eq!(a.socket_dir, format!("{}/test-ma-a", root));
+        assert!(Slot::allocate_in(&root, "sqlite", "test-sl", "/tmp/test-sl")?.is_none());
+
+        // A restart keeps its port, a finished campaign hands it back.
+        let restarted =
+            Slot::allocate_in(&root, "mariadb", "test-ma-a", "/tmp/test-ma-a")?.unwrap();
+        assert_eq!(restarted.port, a.port);
+        assert!(!reserve_port(&root, a.port));
+        release_in(&root, "test-ma-a");
+        assert!(!Path::new(&reservation_file(&root, a.port)).exists());
+        release_in(&root, "test-ma-b");
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    campaign::Campaign,
+    constants::{CAMPAIGN_SECS, FUZZERS_DIR},
+    manifest::{self, Manifest},
+    utils::{self, ShellCommand},
+};
+
+use super::{base_envs, confine, db};
+
+/// SQLsmith against a PostgreSQL server managed for the whole time budget
+pub struct SqlsmithRunner;
+
+#[derive(Default)]
+struct SqlsmithStats {
+    start_time: u64,
+    queries: u64,
+    errors: u64,
+    crashes: u64,
+}
+
+impl SqlsmithRunner {
+    /// Run SQLsmith for the time budget of `campaign`, a day if it has none.
+    pub fn run_pgsql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let pg_envs = base_envs(campaign)?;
+        let log_target = campaign.log_target();
+        let duration = campaign
+            .duration()
+            .unwrap_or(Duration::from_secs(CAMPAIGN_SECS));
+
+        db::init_pgsql(&pg_envs, &log_target)?;
+        db::start_pgsql(&pg_envs, &log_target)?;
+
+        let res = Self::fuzz(&pg_envs, campaign, duration);
+        // Backends only write their coverage profiles once the server shuts down.
+        db::stop_pgsql(&pg_envs, &log_target)?;
+
+        res
+    }
+
+    fn fuzz(
+        envs: &HashMap<&'static str, String>,
+        campaign: &Campaign,
+        duration: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let log_target = campaign.log_target();
+        let stats_file = format!("{}/sqlsmith_stats", envs["TEST_PATH"]);
+        let stats = Arc::new(Mutex::new(SqlsmithStats {
+            start_time: now_secs(),
+            ..Default::default()
+        }));
+
+        let log_file = utils::log_file("run", &log_target, "sqlsmith");
+        if let Some(parent) = std::path::Path::new(&log_file).parent() {
+            utils::create_dir(&parent.display().to_string())?;
+        }
+        let log = Arc::new(Mutex::new(fs::File::create(&log_file)?));
+
+        let mut run_cmd = confine(envs, Some("FUZZER_CORE"));
+        run_cmd.extend([
+            "exec".to_string(),
+            format!("{}/sqlsmith/sqlsmith", FUZZERS_DIR.as_str()),
+            format!(
+                "--target='host=localhost port={} dbname=postgres'",
+                envs["DB_PORT"]
+            ),
+            "--verbose".to_string(),
+        ]);
+        // SQLsmith generates its queries from the schema, it takes no seeds.
+        let fuzzer_source = manifest::builtin_source("sqlsmith");
+        Manifest::new(campaign, fuzzer_source, envs, &run_cmd, None).create()?;
+
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            // The server may still be recovering from a crash SQLsmith found.
+            if !db::pgsql_running(envs) {
+                db::start_pgsql(envs, &log_target)?;
+            }
+
+            let mut child = ShellCommand::new()
+                .args(&run_cmd)
+                .current_dir(campaign.dir())
+                .envs(envs)
+                .pipe_stdio()
+                .spawn()?;
+
+            let stdout = child.stdout.take().map(|out| {
+                let log = log.clone();
+                std::thread::spawn(move || {
+                    for line in BufReader::new(out).lines().map_while(Result::ok) {
+                        let _ = writeln!(log.lock().unwrap(), "{}", line);
+                    }
+                })
+            });
+            let stderr = child.stderr.take().map(|err| {
+                let (log, stats) = (log.clone(), stats.clone());
+                std::thread::spawn(move || count_progress(err, &log, &stats))
+            });
+
+            loop {
+                if child.try_wait()?.is_some() {
+                    break;
+                }
+                if Instant::now() >= deadline {
+                    child.kill()?;
+                    child.wait()?;
+                    break;
+                }
+
+                write_stats(&stats_file, &stats.lock().unwrap())?;
+                std::thread::sleep(Duration::from_secs(1));
+            }
+
+            for handle in [stdout, stderr].into_iter().flatten() {
+                let _ = handle.join();
+            }
+            write_stats(&stats_file, &stats.lock().unwrap())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Count SQLsmith's verbose progress lines, leaving its periodic reports aside.
+///
+/// Each executed query prints `.`, failed ones `e`, `S` (syntax) or `t` (timeout),
+/// and a broken connection, i.e. a server crash, prints `C`.
+fn count_progress<R: Read>(reader: R, log: &Mutex<fs::File>, stats: &Mutex<SqlsmithStats>) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        let _ = writeln!(log.lock().unwrap(), "{}", line);
+
+        if line.is_empty() || !line.chars().all(|c| ".eStC".contains(c)) {
+            continue;
+        }
+
+        let mut stats = stats.lock().unwrap();
+        for c in line.chars() {
+            stats.queries += 1;
+            match c {
+                'e' | 'S' | 't' => stats.errors += 1,
+                'C' => stats.crashes += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Write the stats in the `key : value` layout of AFL's `fuzzer_stats`.
+fn write_stats(path: &str, stats: &SqlsmithStats) -> Result<(), Box<dyn std::error::Error>> {
+    let content = format!(
+        "start_time        : {}\nlast_update       : {}\nqueries           : {}\nerrors            : {}\ncrashes           : {}\n",
+        stats.start_time,
+        now_secs(),
+        stats.queries,
+        stats.errors,
+        stats.crashes
+    );
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_progress() {
+        let output = "\
+............e.S\n\
+queries: 1000 (12.5 gen/s, 10.2 exec/s)\n\
+AST stats (avg): height = 5.2 nodes = 31.4\n\
+12\tERROR:  division by zero\n\
+..tC.\n";
+        let dir = utils::test_dir("sqlsmith");
+        let log = Mutex::new(fs::File::create(dir.join("sqlsmith.log")).unwrap());
+        let stats = Mutex::new(SqlsmithStats::default());
+        count_progress(output.as_bytes(), &log, &stats);
+
+        let stats = stats.lock().unwrap();
+        assert_eq!(stats.queries, 20);
+        assert_eq!(stats.errors, 3);
+        assert_eq!(stats.crashes, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
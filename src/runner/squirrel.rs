@@ -1,166 +1,41 @@
-use std::collections::HashMap;
+use crate::{campaign::Campaign, constants::FUZZERS_DIR};
 
-use crate::{
-    constants::{FUZZERS_DIR, INSTALL_DIR, SQUIRREL_ENVS_TABLE, TEST_DIR},
-    utils::{self, ShellCommand},
-};
-
-use super::Runner;
+use super::{afl_fuzz, campaign_envs, db, Runner};
 
 pub struct SquirrelRunner;
 
 impl Runner for SquirrelRunner {
-    fn run_pgsql() -> Result<(), Box<dyn std::error::Error>> {
-        let fuzzer_test_dir = SQUIRREL_ENVS_TABLE
-            .get("postgresql")
-            .unwrap()
-            .get("TEST_PATH")
-            .unwrap();
-        let fuzzer_db_data_dir = format!("{}/data", fuzzer_test_dir.as_str());
-        utils::create_dir(fuzzer_test_dir)?;
-        utils::create_dir(&fuzzer_db_data_dir)?;
-
-        let pg_envs: HashMap<&str, String> =
-            SQUIRREL_ENVS_TABLE.get("postgresql").unwrap().to_owned();
-
-        let init_cmd = [
-            format!("{}/postgresql/bin/initdb", INSTALL_DIR.as_str()),
-            "-D".to_string(),
-            "$TEST_PATH/data".to_string(),
-        ];
-        ShellCommand::new()
-            .args(&init_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&pg_envs)
-            .spawn()?
-            .wait_with_output()?;
+    fn run_pgsql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let pg_envs = campaign_envs(campaign)?;
 
-        let run_cmd = [
-            format!("{}/squirrel/AFLplusplus/afl-fuzz", FUZZERS_DIR.as_str()),
-            "-i".to_string(),
-            format!(
-                "{}/squirrel/data/fuzz_root/pqsql_input",
-                FUZZERS_DIR.as_str()
-            ),
-            "-o".to_string(),
-            "$TEST_PATH".to_string(),
-            "-t".to_string(),
-            "120000".to_string(),
-            "-S".to_string(),
-            "1".to_string(),
-            "--".to_string(),
-            format!("{}/squirrel/build/db_driver", FUZZERS_DIR.as_str()),
-        ];
-        ShellCommand::new()
-            .args(&run_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&pg_envs)
-            .spawn()?
-            .wait_with_output()?;
-
-        Ok(())
+        db::init_pgsql(&pg_envs, &campaign.log_target())?;
+        afl_fuzz(&pg_envs, campaign, &db_driver())
     }
 
-    fn run_mysql() -> Result<(), Box<dyn std::error::Error>> {
-        let fuzzer_test_dir = SQUIRREL_ENVS_TABLE
-            .get("mysql")
-            .unwrap()
-            .get("TEST_PATH")
-            .unwrap();
-        let fuzzer_db_data_dir = format!("{}/data", fuzzer_test_dir.as_str());
-        utils::create_dir(fuzzer_test_dir)?;
-        utils::create_dir(&fuzzer_db_data_dir)?;
-
-        let my_envs: HashMap<&str, String> = SQUIRREL_ENVS_TABLE.get("mysql").unwrap().to_owned();
-
-        let init_cmd = [
-            format!("{}/mysql/bin/mysqld", INSTALL_DIR.as_str()),
-            "-D".to_string(),
-            "$TEST_PATH/data".to_string(),
-        ];
-        ShellCommand::new()
-            .args(&init_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&my_envs)
-            .spawn()?
-            .wait_with_output()?;
+    fn run_mysql(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let my_envs = campaign_envs(campaign)?;
 
-        let run_cmd = [
-            format!("{}/squirrel/AFLplusplus/afl-fuzz", FUZZERS_DIR.as_str()),
-            "-i".to_string(),
-            format!(
-                "{}/squirrel/data/fuzz_root/mysql_input",
-                FUZZERS_DIR.as_str()
-            ),
-            "-o".to_string(),
-            "$TEST_PATH".to_string(),
-            "-t".to_string(),
-            "120000".to_string(),
-            "-S".to_string(),
-            "1".to_string(),
-            "--".to_string(),
-            format!("{}/squirrel/build/db_driver", FUZZERS_DIR.as_str()),
-        ];
-        ShellCommand::new()
-            .args(&run_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&my_envs)
-            .spawn()?
-            .wait_with_output()?;
-
-        Ok(())
+        db::init_mysql(&my_envs, &campaign.log_target())?;
+        afl_fuzz(&my_envs, campaign, &db_driver())
     }
 
-    fn run_mariadb() -> Result<(), Box<dyn std::error::Error>> {
-        let fuzzer_test_dir = SQUIRREL_ENVS_TABLE
-            .get("mariadb")
-            .unwrap()
-            .get("TEST_PATH")
-            .unwrap();
-        let fuzzer_db_data_dir = format!("{}/data", fuzzer_test_dir.as_str());
-        utils::create_dir(fuzzer_test_dir)?;
-        utils::create_dir(&fuzzer_db_data_dir)?;
-
-        let ma_envs: HashMap<&str, String> = SQUIRREL_ENVS_TABLE.get("mariadb").unwrap().to_owned();
+    fn run_mariadb(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let ma_envs = campaign_envs(campaign)?;
 
-        let init_cmd = [
-            format!(
-                "{}/mariadb/scripts/mariadb-install-db",
-                INSTALL_DIR.as_str()
-            ),
-            format!("--basedir={}/mariadb", INSTALL_DIR.as_str()),
-            format!("--datadir={}/data", fuzzer_db_data_dir),
-        ];
-        ShellCommand::new()
-            .args(&init_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&ma_envs)
-            .spawn()?
-            .wait_with_output()?;
+        db::init_mariadb(&ma_envs, &campaign.log_target())?;
+        afl_fuzz(&ma_envs, campaign, &db_driver())
+    }
 
-        let run_cmd = [
-            format!("{}/squirrel/AFLplusplus/afl-fuzz", FUZZERS_DIR.as_str()),
-            "-i".to_string(),
-            format!(
-                "{}/squirrel/data/fuzz_root/mysql_input",
-                FUZZERS_DIR.as_str()
-            ),
-            "-o".to_string(),
-            "$TEST_PATH".to_string(),
-            "-t".to_string(),
-            "120000".to_string(),
-            "-S".to_string(),
-            "1".to_string(),
-            "--".to_string(),
-            format!("{}/squirrel/build/db_driver", FUZZERS_DIR.as_str()),
-        ];
-        ShellCommand::new()
-            .args(&run_cmd)
-            .current_dir(TEST_DIR.as_str())
-            .envs(&ma_envs)
-            .spawn()?
-            .wait_with_output()?;
+    fn run_sqlite(campaign: &Campaign) -> Result<(), Box<dyn std::error::Error>> {
+        let sl_envs = campaign_envs(campaign)?;
 
-        Ok(())
+        // SQLite is embedded into the harness, so there is no server to initialize.
+        let harness = format!("{}/bin/ossfuzz", sl_envs["SQLITE_INSTALL_PATH"]);
+        afl_fuzz(&sl_envs, campaign, &harness)
     }
 }
+
+/// Squirrel's driver that starts the database server and executes each test case
+pub(super) fn db_driver() -> String {
+    format!("{}/squirrel/build/db_driver", FUZZERS_DIR.as_str())
+}
@@ -0,0 +1,625 @@
+use std::{collections::HashMap, fs, io::Read, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    constants::{CACHE_DIR, SOURCE_MANIFEST, SQLSMITH_VERSION, SQUIRREL_VERSION},
+    fuzzer::FuzzersConfig,
+    target::TargetsConfig,
+    utils::{self, ShellCommand},
+};
+
+pub enum SourceKind {
+    /// Release tarball, cached as `tarballs/<file>` and unpacked from `src_dir`
+    Tarball {
+        url: String,
+        file: String,
+        src_dir: String,
+    },
+    /// Git repository, cached as a bare mirror together with its submodules
+    Git { url: String, reference: String },
+}
+
+pub struct Source {
+    pub name: String,
+    pub version: String,
+    pub kind: SourceKind,
+}
+
+/// Source of one version of a target or fuzzer
+pub fn get(name: &str, version: &str) -> Result<Source, Box<dyn std::error::Error>> {
+    let kind = match name {
+        "squirrel" => SourceKind::Git {
+            url: "https://github.com/s3team/Squirrel.git".to_string(),
+            reference: version.to_string(),
+        },
+        "sqlsmith" => SourceKind::Git {
+            url: "https://github.com/anse1/sqlsmith.git".to_string(),
+            reference: version.to_string(),
+        },
+        "postgresql" => SourceKind::Tarball {
+            url: format!(
+                "https://ftp.postgresql.org/pub/source/v{0}/postgresql-{0}.tar.bz2",
+                version
+            ),
+            file: format!("postgresql-{}.tar.bz2", version),
+            src_dir: format!("postgresql-{}", version),
+        },
+        "boost" => {
+            let version_1 = version.replace('.', "_");
+            SourceKind::Tarball {
+                url: format!(
+                    "https://boostorg.jfrog.io/artifactory/main/release/{}/source/boost_{}.tar.bz2",
+                    version, version_1
+                ),
+                file: format!("boost_{}.tar.bz2", version_1),
+                src_dir: format!("boost_{}", version_1),
+            }
+        }
+        "mysql" => SourceKind::Git {
+            url: "https://github.com/mysql/mysql-server.git".to_string(),
+            reference: version.to_string(),
+        },
+        "mariadb" => SourceKind::Git {
+            url: "https://github.com/MariaDB/server.git".to_string(),
+            reference: version.to_string(),
+        },
+        "sqlite" => SourceKind::Tarball {
+            url: format!(
+                "https://github.com/sqlite/sqlite/archive/refs/tags/version-{}.tar.gz",
+                version
+            ),
+            file: format!("sqlite-version-{}.tar.gz", version),
+            src_dir: format!("sqlite-version-{}", version),
+        },
+        _ => return Err(format!("Unknown source {}", name).into()),
+    };
+
+    Ok(Source {
+        name: name.to_string(),
+        version: version.to_string(),
+        kind,
+    })
+}
+
+/// Download every configured source into the cache, pinning unpinned checksums if `pin` is set.
+pub fn fetch_all(
+    targets: &TargetsConfig,
+    fuzzers: &FuzzersConfig,
+    pin: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sources = vec![
+        get("squirrel", SQUIRREL_VERSION)?,
+        get("sqlsmith", SQLSMITH_VERSION)?,
+    ];
+    for name in ["postgresql", "boost", "mysql", "mariadb", "sqlite"] {
+        for version in targets.versions(name) {
+            sources.push(get(name, &version)?);
+        }
+    }
+    sources.extend(
+        fuzzers
+            .iter()
+            .filter_map(|(name, fuzzer)| fuzzer.source(name)),
+    );
+
+    let mut manifest = Manifest::load(SOURCE_MANIFEST.as_str())?;
+    for source in sources {
+        source.fetch(&mut manifest, pin)?;
+    }
+    if pin {
+        manifest.save(SOURCE_MANIFEST.as_str())?;
+    }
+
+    Ok(())
+}
+
+impl Source {
+    /// Fetch the source into the cache and verify it against the manifest.
+    pub fn fetch(
+        &self,
+        manifest: &mut Manifest,
+        pin: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let key = self.key();
+        match &self.kind {
+            SourceKind::Tarball { url, file, .. } => {
+                let cached = tarball_path(file);
+                if !Path::new(&cached).exists() {
+                    utils::create_dir(&format!("{}/tarballs", CACHE_DIR.as_str()))?;
+                    let partial = format!("{}.part", cached);
+                    let download_cmd = ["wget", "-O", &partial, url];
+                    ShellCommand::new()
+                        .args(&download_cmd)
+                        .current_dir(CACHE_DIR.as_str())
+                        .log_to(self.log_file("fetch", "download"))
+                        .run()?;
+                    fs::rename(&partial, &cached)?;
+                }
+
+                let digest = sha256_file(&cached)?;
+                match manifest.get(&key) {
+                    Some(pinned) if pinned == digest => {}
+                    Some(pinned) => return Err(mismatch(&key, pinned, &digest)),
+                    None if pin => manifest.set(&key, &digest),
+                    None => return Err(unpinned(&key)),
+                }
+            }
+            SourceKind::Git { url, reference } => {
+                let mirror = mirror_git(&self.log_target(), url)?;
+                let pinned = match manifest.get(&key) {
+                    Some(pinned) => pinned.to_string(),
+                    None if pin => {
+                        let commit = git_output(
+                            &mirror,
+                            &["rev-parse", &format!("{}^{{commit}}", reference)],
+                        )?;
+                        manifest.set(&key, &commit);
+                        commit
+                    }
+                    None => return Err(unpinned(&key)),
+                };
+
+                // Branches move on, so only the pinned commit has to be present.
+                git_output(
+                    &mirror,
+                    &["cat-file", "-e", &format!("{}^{{commit}}", pinned)],
+                )
+                .map_err(|_| format!("Pinned commit {} of {} is not in {}", pinned, key, mirror))?;
+
+                let mut urls = vec![url.clone()];
+                mirror_submodules(&self.log_target(), url, &mirror, &pinned, &mut urls)?;
+                fs::write(self.urls_index(), urls.join("\n"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Place a verified copy of the source at `dest`, consulting the cache first.
+    pub fn checkout(&self, dest: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let key = self.key();
+        let mut manifest = Manifest::load(SOURCE_MANIFEST.as_str())?;
+        if !self.is_cached() {
+            self.fetch(&mut manifest, false)?;
+        }
+        let pinned = manifest
+            .get(&key)
+            .ok_or_else(|| unpinned(&key))?
+            .to_string();
+
+        let dest_path = Path::new(dest);
+        let parent = dest_path
+            .parent()
+            .ok_or_else(|| format!("Invalid source destination {}", dest))?;
+        utils::create_dir(&parent.display().to_string())?;
+
+        match &self.kind {
+            SourceKind::Tarball { file, src_dir, .. } => {
+                let cached = tarball_path(file);
+                let digest = sha256_file(&cached)?;
+                if digest != pinned {
+                    return Err(mismatch(&key, &pinned, &digest));
+                }
+
+                let extract_cmd = ["tar", "xf", &cached];
+                ShellCommand::new()
+                    .args(&extract_cmd)
+                    .current_dir(parent)
+                    .log_to(self.log_file("build", "extract"))
+                    .run()?;
+                fs::rename(parent.join(src_dir), dest_path)?;
+            }
+            SourceKind::Git { url, .. } => {
+                // Rewrite every mirrored url so nested submodules are cloned offline too.
+                let urls = fs::read_to_string(self.urls_index())?;
+                let mut configs = urls
+                    .lines()
+                    .filter(|u| !u.is_empty())
+                    .map(|u| (format!("url.{}.insteadOf", mirror_path(u)), u.to_string()))
+                    .collect::<Vec<_>>();
+                // Submodules cloned from local mirrors need the file transport.
+                configs.push(("protocol.file.allow".to_string(), "always".to_string()));
+
+                let mut envs = vec![("GIT_CONFIG_COUNT".to_string(), configs.len().to_string())];
+                for (i, (key, value)) in configs.into_iter().enumerate() {
+                    envs.push((format!("GIT_CONFIG_KEY_{}", i), key));
+                    envs.push((format!("GIT_CONFIG_VALUE_{}", i), value));
+                }
+
+                let clone_cmd = ["git", "clone", "--no-checkout", url, dest];
+                ShellCommand::new()
+                    .args(&clone_cmd)
+                    .current_dir(parent)
+                    .envs(envs.clone())
+                    .log_to(self.log_file("build", "git-clone"))
+                    .run()?;
+
+                let checkout_cmd = [
+                    "git",
+                    "checkout",
+                    &pinned,
+                    "&&",
+                    "git",
+                    "submodule",
+                    "update",
+                    "--init",
+                    "--recursive",
+                ];
+                ShellCommand::new()
+                    .args(&checkout_cmd)
+                    .current_dir(dest)
+                    .envs(envs)
+                    .log_to(self.log_file("build", "git-checkout"))
+                    .run()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pinned digest of this source, if any
+    pub fn pinned(&self) -> Option<String> {
+        Manifest::load(SOURCE_MANIFEST.as_str())
+            .ok()?
+            .get(&self.key())
+            .map(|e| e.to_string())
+    }
+
+    /// Manifest entry of this source, e.g. `postgresql@15.0`
+    pub fn key(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    fn log_target(&self) -> String {
+        format!("{}-{}", self.name, self.version)
+    }
+
+    fn log_file(&self, kind: &str, step: &str) -> String {
+        utils::log_file(kind, &self.log_target(), step)
+    }
+
+    fn urls_index(&self) -> String {
+        format!("{}/git/{}.urls", CACHE_DIR.as_str(), self.key())
+    }
+
+    fn is_cached(&self) -> bool {
+        match &self.kind {
+            SourceKind::Tarball { file, .. } => Path::new(&tarball_path(file)).exists(),
+            SourceKind::Git { url, .. } => {
+                Path::new(&mirror_path(url)).exists() && Path::new(&self.urls_index()).exists()
+            }
+        }
+    }
+}
+
+/// Pinned SHA-256 of tarballs and commit ids of git sources, one `<name>@<version> <digest>` per line
+pub struct Manifest {
+    entries: HashMap<String, String>,
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut entries = HashMap::new();
+        if Path::new(path).exists() {
+            entries = Self::parse(&fs::read_to_string(path)?)?;
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn parse(content: &str) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+        let mut entries = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut items = line.split_whitespace();
+            match (items.next(), items.next(), items.next()) {
+                (Some(name), Some(digest), None) => {
+                    entries.insert(name.to_string(), digest.to_lowercase());
+                }
+                _ => return Err(format!("Malformed manifest line: {}", line).into()),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut names = self.entries.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut content = String::from(
+            "# Pinned sources: SHA-256 for tarballs, commit id for git repositories.\n\
+             # Populate with `fuzzer_compare fetch --pin` on a connected machine.\n",
+        );
+        for name in names {
+            content.push_str(&format!("{} {}\n", name, self.entries[name]));
+        }
+
+        fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(|e| e.as_str())
+    }
+
+    pub fn set(&mut self, name: &str, digest: &str) {
+        self.entries.insert(name.to_string(), digest.to_string());
+    }
+}
+
+fn mismatch(key: &str, pinned: &str, digest: &str) -> Box<dyn std::error::Error> {
+    format!(
+        "Source {} does not match the manifest: expected {}, got {}",
+        key, pinned, digest
+    )
+    .into()
+}
+
+fn unpinned(name: &str) -> Box<dyn std::error::Error> {
+    format!(
+        "Source {} is not pinned in {}, run `fuzzer_compare fetch --pin` on a connected machine",
+        name,
+        SOURCE_MANIFEST.as_str()
+    )
+    .into()
+}
+
+fn tarball_path(file: &str) -> String {
+    format!("{}/tarballs/{}", CACHE_DIR.as_str(), file)
+}
+
+/// Bare mirror location of a git url
+fn mirror_path(url: &str) -> String {
+    let escaped = url
+        .trim_end_matches(".git")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    format!("{}/git/{}.git", CACHE_DIR.as_str(), escaped)
+}
+
+fn mirror_git(log_target: &str, url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mirror = mirror_path(url);
+    let repo = url
+        .trim_end_matches(".git")
+        .rsplit('/')
+        .next()
+        .unwrap_or(url);
+    let log_file = utils::log_file("fetch", log_target, &format!("git-mirror-{}", repo));
+    if Path::new(&mirror).exists() {
+        let update_cmd = ["git", "remote", "update", "--prune"];
+        ShellCommand::new()
+            .args(&update_cmd)
+            .current_dir(&mirror)
+            .log_to(&log_file)
+            .run()?;
+    } else {
+        utils::create_dir(&format!("{}/git", CACHE_DIR.as_str()))?;
+        let mirror_cmd = ["git", "clone", "--mirror", url, &mirror];
+        ShellCommand::new()
+            .args(&mirror_cmd)
+            .current_dir(CACHE_DIR.as_str())
+            .log_to(&log_file)
+            .run()?;
+    }
+
+    Ok(mirror)
+}
+
+/// Mirror the submodules recorded at `commit` of the repository cloned from `url`, recursively.
+fn mirror_submodules(
+    log_target: &str,
+    url: &str,
+    mirror: &str,
+    commit: &str,
+    urls: &mut Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let gitmodules = match git_output(mirror, &["show", &format!("{}:.gitmodules", commit)]) {
+        Ok(inner) => inner,
+        Err(_) => return Ok(()),
+    };
+
+    for (path, sub_url) in parse_gitmodules(&gitmodules) {
+        // Git resolves relative urls against the upstream of the clone, so they are mirrored
+        // and rewritten under the url they resolve to.
+        let sub_url = submodule_url(url, &sub_url)?;
+        if urls.contains(&sub_url) {
+            continue;
+        }
+
+        let sub_mirror = mirror_git(log_target, &sub_url)?;
+        urls.push(sub_url.clone());
+
+        let tree = git_output(mirror, &["ls-tree", commit, &path])?;
+        if let Some(sub_commit) = tree.split_whitespace().nth(2) {
+            mirror_submodules(log_target, &sub_url, &sub_mirror, sub_commit, urls)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Absolute url of a submodule of the repository at `super_url`,
+/// resolving `./` and `../` urls the way `git submodule` does
+fn submodule_url(super_url: &str, url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if !url.starts_with("./") && !url.starts_with("../") {
+        return Ok(url.to_string());
+    }
+
+    let mut base = super_url.trim_end_matches('/').to_string();
+    let mut rest = url;
+    loop {
+        if let Some(tail) = rest.strip_prefix("./") {
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("../") {
+            // Stop at the host, e.g. `https://host` or `git@host:`.
+            let parent = match base.rsplit_once('/') {
+                Some((parent, _)) if !parent.ends_with('/') && !parent.is_empty() => {
+                    parent.to_string()
+                }
+                _ => match base.split_once(':') {
+                    Some((host, path)) if !path.is_empty() && !path.starts_with('/') => {
+                        format!("{}:", host)
+                    }
+                    _ => {
+                        return Err(
+                            format!("Submodule url {} reaches above {}", url, super_url).into()
+                        )
+                    }
+                },
+            };
+            base = parent;
+            rest = tail;
+        } else {
+            break;
+        }
+    }
+
+    if base.ends_with(':') {
+        Ok(format!("{}{}", base, rest))
+    } else {
+        Ok(format!("{}/{}", base, rest))
+    }
+}
+
+/// `(path, url)` of every submodule in a `.gitmodules` file
+fn parse_gitmodules(content: &str) -> Vec<(String, String)> {
+    let mut modules = vec![];
+    let mut path = None;
+    let mut url = None;
+    for line in content.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            if let (Some(p), Some(u)) = (path.take(), url.take()) {
+                modules.push((p, u));
+            }
+        } else if let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "path" => path = Some(value.trim().to_string()),
+                "url" => url = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    if let (Some(p), Some(u)) = (path, url) {
+        modules.push((p, u));
+    }
+
+    modules
+}
+
+fn git_output(dir: &str, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "`git {}` failed in {}: {}",
+            args.join(" "),
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+pub fn sha256_file(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() -> Result<(), Box<dyn std::error::Error>> {
+        let entries = Manifest::parse("# comment\n\npostgresql ABCDEF\nmysql 0123\n")?;
+        assert_eq!(
+            entries.get("postgresql").map(|e| e.as_str()),
+            Some("abcdef")
+        );
+        assert_eq!(entries.get("mysql").map(|e| e.as_str()), Some("0123"));
+        assert!(Manifest::parse("postgresql").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_submodule_url() -> Result<(), Box<dyn std::error::Error>> {
+        let modules =
+            parse_gitmodules("[submodule \"deps\"]\n\tpath = deps\n\turl = ../deps.git\n");
+        let super_url = "https://github.com/org/repo.git";
+        assert_eq!(
+            submodule_url(super_url, &modules[0].1)?,
+            "https://github.com/org/deps.git"
+        );
+        assert_eq!(
+            submodule_url(super_url, "./sub")?,
+            "https://github.com/org/repo.git/sub"
+        );
+        assert_eq!(
+            submodule_url("git@github.com:org/repo.git", "../../other/deps.git")?,
+            "git@github.com:other/deps.git"
+        );
+        assert_eq!(
+            submodule_url(super_url, "https://example.com/x.git")?,
+            "https://example.com/x.git"
+        );
+        assert!(submodule_url(super_url, "../../../deps.git").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_gitmodules() {
+        let modules = parse_gitmodules(
+            "[submodule \"AFLplusplus\"]\n\tpath = AFLplusplus\n\turl = https://github.com/AFLplusplus/AFLplusplus\n",
+        );
+        assert_eq!(
+            modules,
+            vec![(
+                "AFLplusplus".to_string(),
+                "https://github.com/AFLplusplus/AFLplusplus".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_sha256_file() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = utils::test_dir("sha256");
+        let path = dir.join("abc");
+        fs::write(&path, "abc")?;
+        assert_eq!(
+            sha256_file(&path.display().to_string())?,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::constants::{
+    BOOST_VERSION, DB_DIR, INSTALL_DIR, MARIADB_VERSION, MYSQL_VERSION, PGSQL_VERSION,
+    SQLITE_VERSION,
+};
+
+/// Versions of every target, read from `config/targets.toml`
+#[derive(Deserialize, Default)]
+pub struct TargetsConfig {
+    #[serde(flatten)]
+    targets: HashMap<String, TargetConfig>,
+
+    /// Versions chosen on the command line for this run
+    #[serde(skip)]
+    selected: HashMap<String, String>,
+
+    /// Targets rebuilt regardless of their build stamps
+    #[serde(skip)]
+    forced: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TargetConfig {
+    /// Git tag/commit or release version, all built side by side
+    pub versions: Vec<String>,
+}
+
+impl TargetsConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Self = toml::from_str(content)?;
+        for (name, target) in &config.targets {
+            if default_version(name).is_none() {
+                return Err(format!("Unknown target {}", name).into());
+            }
+            if target.versions.is_empty() {
+                return Err(format!("No version configured for {}", name).into());
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// All versions of `target` to build
+    pub fn versions(&self, target: &str) -> Vec<String> {
+        match self.targets.get(target) {
+            Some(inner) => inner.versions.clone(),
+            None => default_version(target).into_iter().collect(),
+        }
+    }
+
+    /// Fuzz `version` of `target` instead of its first configured version.
+    pub fn select(
+        &mut self,
+        target: &str,
+        version: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if default_version(target).is_none() {
+            return Err(format!("Unknown target {}", target).into());
+        }
+        self.selected
+            .insert(target.to_string(), version.to_string());
+
+        Ok(())
+    }
+
+    /// Rebuild every step of `target`, or of everything for `all`.
+    pub fn force(&mut self, target: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !matches!(target, "all" | "squirrel" | "sqlsmith") && default_version(target).is_none() {
+            return Err(format!("Unknown target {}", target).into());
+        }
+        self.forced.push(target.to_string());
+
+        Ok(())
+    }
+
+    /// Rebuild a fuzzer defined in `config/fuzzers.toml`, which validates its name.
+    pub fn force_fuzzer(&mut self, name: &str) {
+        self.forced.push(name.to_string());
+    }
+
+    pub fn forced(&self, target: &str) -> bool {
+        self.forced.iter().any(|t| t == target || t == "all")
+    }
+
+    /// The version of `target` campaigns run against
+    pub fn selected(&self, target: &str) -> String {
+        match self.selected.get(target) {
+            Some(inner) => inner.clone(),
+            None => self.versions(target).remove(0),
+        }
+    }
+}
+
+fn default_version(target: &str) -> Option<String> {
+    let version = match target {
+        "postgresql" => PGSQL_VERSION,
+        "mysql" => MYSQL_VERSION,
+        "mariadb" => MARIADB_VERSION,
+        "boost" => BOOST_VERSION,
+        "sqlite" => SQLITE_VERSION,
+        _ => return None,
+    };
+
+    Some(version.to_string())
+}
+
+/// Source directory of one version of a target
+pub fn src_dir(target: &str, version: &str) -> String {
+    format!("{}/{}-{}", DB_DIR.as_str(), target, version)
+}
+
+/// Install prefix of one version of a target
+pub fn install_dir(target: &str, version: &str) -> String {
+    format!("{}/{}-{}", INSTALL_DIR.as_str(), target, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_config() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = TargetsConfig::parse(
+            r#"
+            [postgresql]
+            versions = ["15.0", "16.1"]
+            "#,
+        )?;
+
+        assert_eq!(config.versions("postgresql"), vec!["15.0", "16.1"]);
+        assert_eq!(config.selected("postgresql"), "15.0");
+        assert_eq!(config.selected("mysql"), MYSQL_VERSION);
+
+        config.select("postgresql", "16.1")?;
+        assert_eq!(config.selected("postgresql"), "16.1");
+        assert!(config.select("oracle", "23c").is_err());
+        assert!(TargetsConfig::parse("[oracle]\nversions = [\"23c\"]").is_err());
+
+        assert!(!config.forced("mysql"));
+        config.force("squirrel")?;
+        assert!(config.forced("squirrel") && !config.forced("mysql"));
+        config.force("all")?;
+        assert!(config.forced("mysql"));
+
+        Ok(())
+    }
+}
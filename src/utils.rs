@@ -1,9 +1,37 @@
-use std::{ffi::OsStr, process::Stdio};
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::constants::{LOG_DIR, NPROCS};
+
+/// Whether logged command output is also streamed to the terminal
+static LIVE_LOG: AtomicBool = AtomicBool::new(false);
+
+pub fn set_live_log(enable: bool) {
+    LIVE_LOG.store(enable, Ordering::Relaxed);
+}
+
+pub fn live_log() -> bool {
+    LIVE_LOG.load(Ordering::Relaxed)
+}
 
-use crate::constants::NPROCS;
+/// Log file of one step, e.g. `logs/build/mysql/cmake-config.log`
+pub fn log_file(kind: &str, target: &str, step: &str) -> String {
+    format!("{}/{}/{}/{}.log", LOG_DIR.as_str(), kind, target, step)
+}
 
 pub struct ShellCommand {
     cmd: std::process::Command,
+    line: String,
+    log_file: Option<PathBuf>,
 }
 
 impl Default for ShellCommand {
@@ -15,7 +43,11 @@ impl Default for ShellCommand {
 impl ShellCommand {
     pub fn new() -> Self {
         let cmd = std::process::Command::new("/bin/sh");
-        ShellCommand { cmd }
+        ShellCommand {
+            cmd,
+            line: String::new(),
+            log_file: None,
+        }
     }
 
     pub fn args<S: AsRef<str>>(&mut self, args: &[S]) -> &mut ShellCommand {
@@ -26,6 +58,7 @@ impl ShellCommand {
             .join(" ");
 
         self.cmd.arg("-c").arg(&args);
+        self.line = args;
 
         self
     }
@@ -53,11 +86,85 @@ impl ShellCommand {
         self
     }
 
+    /// Tee stdout and stderr into `path` when the command is `run`.
+    pub fn log_to<P: AsRef<Path>>(&mut self, path: P) -> &mut ShellCommand {
+        self.log_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     pub fn spawn(&mut self) -> Result<std::process::Child, Box<dyn std::error::Error>> {
         let child = self.cmd.spawn()?;
 
         Ok(child)
     }
+
+    /// Run the command to completion, failing on a non-zero exit status.
+    ///
+    /// With a log file, its stdout and stderr are written there line by line
+    /// and also streamed to the terminal if live logging is enabled.
+    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let log_file = match &self.log_file {
+            Some(inner) => inner.clone(),
+            None => {
+                let status = self.cmd.status()?;
+                if !status.success() {
+                    return Err(format!("`{}` failed with {}", self.line, status).into());
+                }
+                return Ok(());
+            }
+        };
+
+        if let Some(parent) = log_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let log = Arc::new(Mutex::new(fs::File::create(&log_file)?));
+        writeln!(log.lock().unwrap(), "$ {}", self.line)?;
+
+        let mut child = self
+            .cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().map(|out| tee(out, log.clone(), false));
+        let stderr = child.stderr.take().map(|err| tee(err, log.clone(), true));
+        let status = child.wait()?;
+        for handle in [stdout, stderr].into_iter().flatten() {
+            let _ = handle.join();
+        }
+
+        if !status.success() {
+            return Err(format!(
+                "`{}` failed with {}, see {}",
+                self.line,
+                status,
+                log_file.display()
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+fn tee<R: Read + Send + 'static>(
+    reader: R,
+    log: Arc<Mutex<fs::File>>,
+    is_stderr: bool,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            let _ = writeln!(log.lock().unwrap(), "{}", line);
+            if live_log() {
+                if is_stderr {
+                    eprintln!("{}", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+        }
+    })
 }
 
 pub fn create_dir(path: &str) -> Result<(), Box<dyn std::error::Error>> {
@@ -80,31 +187,45 @@ pub fn remove_dir(path: &str) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Parse a duration like `90s`, `30m`, `24h` or plain seconds.
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, Box<dyn std::error::Error>> {
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("Unknown duration unit in {}", s).into()),
+    };
+
+    Ok(std::time::Duration::from_secs(num.parse::<u64>()? * scale))
+}
+
 pub fn git_clone(
     url: &str,
     target_location: Option<&str>,
+    log_file: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = vec!["git", "clone", "--recursive", url];
     if let Some(location) = target_location {
         cmd.push(location);
     }
 
-    let output = ShellCommand::new()
+    ShellCommand::new()
         .args(&cmd)
         .current_dir(".")
-        .spawn()?
-        .wait_with_output()?;
-    for l in String::from_utf8(output.stdout)?.lines() {
-        println!("{:?}", l);
-    }
-
-    Ok(())
+        .log_to(log_file)
+        .run()
 }
 
 pub fn cmake_config<S: AsRef<str>>(
     src_dir: &str,
     bin_dir: Option<&str>,
     cmake_vars: Option<&[S]>,
+    log_file: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let bin_dir = if let Some(inner) = bin_dir {
         inner.to_owned()
@@ -132,10 +253,8 @@ pub fn cmake_config<S: AsRef<str>>(
     ShellCommand::new()
         .args(&cmd)
         .current_dir(src_dir)
-        .spawn()?
-        .wait_with_output()?;
-
-    Ok(())
+        .log_to(log_file)
+        .run()
 }
 
 pub fn cmake_build<S: AsRef<str>>(
@@ -144,6 +263,7 @@ pub fn cmake_build<S: AsRef<str>>(
     build_args: Option<&[S]>,
     target: Option<&str>,
     nproc: Option<i8>,
+    log_file: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let bin_dir = if let Some(inner) = bin_dir {
         inner.to_owned()
@@ -178,10 +298,21 @@ pub fn cmake_build<S: AsRef<str>>(
     ShellCommand::new()
         .args(&cmd)
         .current_dir(src_dir)
-        .spawn()?
-        .wait_with_output()?;
+        .log_to(log_file)
+        .run()
+}
 
-    Ok(())
+/// Empty directory of its own for the test `name`, so parallel and repeated runs do not collide
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "fuzzer_compare_test_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Failed to create the test directory");
+    dir
 }
 
 #[cfg(test)]
@@ -203,4 +334,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_shell_command_log() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = test_dir("log");
+        let log = dir.join("echo.log");
+        ShellCommand::new()
+            .args(&["echo", "out", "&&", "echo", "err", "1>&2"])
+            .log_to(&log)
+            .run()?;
+
+        let content = fs::read_to_string(&log)?;
+        assert!(content.contains("out"));
+        assert!(content.contains("err"));
+
+        let err = ShellCommand::new()
+            .args(&["exit", "3"])
+            .log_to(&log)
+            .run()
+            .unwrap_err();
+        assert!(err.to_string().contains(&log.display().to_string()));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("3600").unwrap().as_secs(), 3600);
+        assert_eq!(parse_duration("90s").unwrap().as_secs(), 90);
+        assert_eq!(parse_duration("30m").unwrap().as_secs(), 1800);
+        assert_eq!(parse_duration("24h").unwrap().as_secs(), 86400);
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("h").is_err());
+    }
 }
@@ -0,0 +1,375 @@
+use std::{fs, net::SocketAddr, path::Path};
+
+use crate::{
+    campaign::{self, Campaign},
+    http::{self, Response},
+    queue::Queue,
+    report,
+};
+
+const HTML: &str = "text/html; charset=utf-8";
+
+/// Seconds between reloads of the pages listing runs and campaigns
+const REFRESH_SECS: u32 = 30;
+
+/// Columns of the monitor CSV shown in the run table, from the last sample
+const SUMMARY_COLUMNS: [&str; 5] = [
+    "health",
+    "edge_covered",
+    "crash_num",
+    "line_cov_percent",
+    "branch_cov_percent",
+];
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; text-align: left; }
+canvas { border: 1px solid #ddd; margin: 0 1em 1em 0; }
+pre { background: #f6f6f6; padding: 1em; overflow: auto; }";
+
+/// Draws the charts of a campaign page from its samples, refreshed every five minutes
+const CHARTS_JS: &str = r##"
+const CHARTS = [
+  ["edge_covered", "Edges"],
+  ["crash_num", "Crashes"],
+  ["line_cov_percent", "Line coverage %"],
+  ["branch_cov_percent", "Branch coverage %"],
+  ["exec_percent", "Valid statements %"],
+];
+
+function draw(canvas, title, times, values) {
+  const ctx = canvas.getContext("2d");
+  const points = values.map((v, i) => [i, v]).filter(([, v]) => v !== null && v >= 0);
+  ctx.clearRect(0, 0, canvas.width, canvas.height);
+  ctx.fillStyle = "#000";
+  ctx.font = "14px sans-serif";
+  if (points.length === 0) {
+    ctx.fillText(title + ": no data", 10, 20);
+    return;
+  }
+  const last = points[points.length - 1][1];
+  const max = Math.max(...points.map(([, v]) => v)) || 1;
+  ctx.fillText(title + ": " + last + " (max " + max + ")", 10, 20);
+  ctx.font = "11px sans-serif";
+  ctx.fillText(times[0], 10, canvas.height - 6);
+  ctx.fillText(times[times.length - 1], canvas.width - 130, canvas.height - 6);
+  const x = (i) => 10 + (canvas.width - 20) * i / Math.max(values.length - 1, 1);
+  const y = (v) => canvas.height - 24 - (canvas.height - 54) * v / max;
+  ctx.strokeStyle = "#2a6fdb";
+  ctx.lineWidth = 2;
+  ctx.beginPath();
+  points.forEach(([i, v], n) => n === 0 ? ctx.moveTo(x(i), y(v)) : ctx.lineTo(x(i), y(v)));
+  ctx.stroke();
+}
+
+async function refresh() {
+  const samples = await (await fetch(SAMPLES)).json();
+  const charts = document.getElementById("charts");
+  charts.replaceChildren();
+  for (const [column, title] of CHARTS) {
+    const canvas = document.createElement("canvas");
+    canvas.width = 480;
+    canvas.height = 220;
+    charts.appendChild(canvas);
+    draw(canvas, title, samples.timestamp || [], samples[column] || []);
+  }
+}
+
+refresh();
+setInterval(refresh, 5 * 60 * 1000);
+"##;
+
+/// Browse the runs on `http://<addr>/` until the process exits.
+///
+/// Pages are read from the same files `status`, `report` and `triage` use,
+/// so the dashboard runs alongside any number of runs.
+pub fn serve(addr: &str) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    http::serve(addr, |target| {
+        let path = target.split('?').next().unwrap_or_default();
+        let parts = path
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>();
+        match route(&parts) {
+            Ok(Some(response)) => response,
+            Ok(None) => Response::not_found(),
+            Err(e) => Response {
+                status: 500,
+                content_type: "text/plain; charset=utf-8",
+                body: format!("{}\n", e).into_bytes(),
+            },
+        }
+    })
+}
+
+fn route(parts: &[&str]) -> Result<Option<Response>, Box<dyn std::error::Error>> {
+    let runs = campaign::runs();
+    let response = match parts {
+        [] => Response::ok(HTML, index(&runs)),
+        ["runs", run, rest @ ..] if runs.iter().any(|r| r == run) => match rest {
+            [] => Response::ok(HTML, run_page(run)?),
+            ["report"] => Response::ok(HTML, report_page(run)?),
+            ["report.csv"] => match fs::read(format!("{}/report.csv", campaign::run_dir(run))) {
+                Ok(content) => Response::ok("text/csv; charset=utf-8", content),
+                Err(_) => return Ok(None),
+            },
+            ["bugs"] => Response::ok(HTML, bugs_page(run)?),
+            ["crashes", id, digest] => match crash_file(run, id, digest) {
+                Some(path) => Response::ok("application/octet-stream", fs::read(path)?),
+                None => return Ok(None),
+            },
+            [id] => match find_campaign(run, id)? {
+                Some(c) => Response::ok(HTML, campaign_page(run, &c)),
+                None => return Ok(None),
+            },
+            [id, "samples.json"] => match find_campaign(run, id)? {
+                Some(c) => Response::ok("application/json", samples(&c.output_csv())?.to_string()),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(response))
+}
+
+fn index(runs: &[String]) -> String {
+    let mut rows = vec![];
+    for run in runs.iter().rev() {
+        let state = match campaign::running_pid(run) {
+            Some(pid) => format!("running as pid {}", pid),
+            None => "stopped".to_string(),
+        };
+        let jobs = Queue::load(run)
+            .map(|q| q.progress().to_string())
+            .unwrap_or("-".to_string());
+        rows.push(vec![
+            link(&format!("/runs/{}", run), run),
+            escape(&state),
+            escape(&jobs),
+        ]);
+    }
+
+    page("Runs", true, &table(&["run", "state", "jobs"], &rows))
+}
+
+fn run_page(run: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut header = vec!["campaign"];
+    header.extend(SUMMARY_COLUMNS);
+    let mut rows = vec![];
+    for c in campaign::load(run)? {
+        let last = report::last_row(&c.output_csv(), &SUMMARY_COLUMNS)
+            .ok()
+            .flatten()
+            .unwrap_or(vec![None; SUMMARY_COLUMNS.len()]);
+        let mut row = vec![link(&format!("/runs/{}/{}", run, c.id()), &c.id())];
+        row.extend(last.iter().map(|v| escape(v.as_deref().unwrap_or("-"))));
+        rows.push(row);
+    }
+
+    let body = format!(
+        "<p>{} | {} | {}</p>{}",
+        link("/", "all runs"),
+        link(&format!("/runs/{}/report", run), "report"),
+        link(&format!("/runs/{}/bugs", run), "triaged bugs"),
+        table(&header, &rows)
+    );
+    Ok(page(run, true, &body))
+}
+
+fn campaign_page(run: &str, c: &Campaign) -> String {
+    let manifest = fs::read_to_string(format!("{}/manifest.json", c.dir()))
+        .unwrap_or("No manifest recorded yet".to_string());
+    let body = format!(
+        "<p>{}</p><div id=\"charts\"></div><h2>Manifest</h2><pre>{}</pre>\
+         <script>const SAMPLES = \"/runs/{}/{}/samples.json\";{}</script>",
+        link(&format!("/runs/{}", run), run),
+        escape(&manifest),
+        run,
+        c.id(),
+        CHARTS_JS
+    );
+
+    page(&c.id(), false, &body)
+}
+
+fn report_page(run: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let report_csv = format!("{}/report.csv", campaign::run_dir(run));
+    let back = link(&format!("/runs/{}", run), run);
+    if !Path::new(&report_csv).exists() {
+        let body = format!(
+            "<p>{}</p><p>No report yet, run <code>report {}</code> once the run is over.</p>",
+            back,
+            escape(run)
+        );
+        return Ok(page("Report", false, &body));
+    }
+
+    let mut rdr = csv::Reader::from_path(&report_csv)?;
+    let header = rdr.headers()?.iter().map(escape).collect::<Vec<_>>();
+    let rows = rdr
+        .records()
+        .map(|r| r.map(|r| r.iter().map(escape).collect()))
+        .collect::<Result<Vec<Vec<_>>, _>>()?;
+    let header = header.iter().map(|h| h.as_str()).collect::<Vec<_>>();
+    let body = format!(
+        "<p>{} | {}</p>{}",
+        back,
+        link(&format!("/runs/{}/report.csv", run), "report.csv"),
+        table(&header, &rows)
+    );
+
+    Ok(page("Report", false, &body))
+}
+
+fn bugs_page(run: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let crashes_dir = format!("{}/crashes", campaign::run_dir(run));
+    let mut rows = vec![];
+    for c in campaign::load(run)? {
+        let mut crashes = fs::read_dir(format!("{}/{}", crashes_dir, c.id()))
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| Some((e.file_name().into_string().ok()?, e.metadata().ok()?.len())))
+            .collect::<Vec<_>>();
+        crashes.sort();
+        for (digest, len) in crashes {
+            let url = format!("/runs/{}/crashes/{}/{}", run, c.id(), digest);
+            rows.push(vec![escape(&c.id()), link(&url, &digest), len.to_string()]);
+        }
+    }
+
+    let body = if rows.is_empty() {
+        format!(
+            "<p>No triaged crashes, run <code>triage {}</code> first.</p>",
+            escape(run)
+        )
+    } else {
+        table(&["campaign", "crash", "bytes"], &rows)
+    };
+    let back = link(&format!("/runs/{}", run), run);
+    Ok(page(
+        "Triaged bugs",
+        false,
+        &format!("<p>{}</p>{}", back, body),
+    ))
+}
+
+fn find_campaign(run: &str, id: &str) -> Result<Option<Campaign>, Box<dyn std::error::Error>> {
+    Ok(campaign::load(run)?.into_iter().find(|c| c.id() == id))
+}
+
+/// Triaged crash `digest` of campaign `id`, if it exists
+fn crash_file(run: &str, id: &str, digest: &str) -> Option<String> {
+    // Digests are hex, so a crafted one cannot leave the crashes directory.
+    if digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    find_campaign(run, id).ok()??;
+
+    let path = format!("{}/crashes/{}/{}", campaign::run_dir(run), id, digest);
+    Path::new(&path).is_file().then_some(path)
+}
+
+/// Timestamps and numeric columns of a monitor CSV, `null` where unknown
+fn samples(csv_file: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut columns = serde_json::Map::new();
+    if !Path::new(csv_file).exists() {
+        return Ok(columns.into());
+    }
+
+    let mut rdr = csv::Reader::from_path(csv_file)?;
+    let headers = rdr.headers()?.clone();
+    let rows = rdr.records().filter_map(|r| r.ok()).collect::<Vec<_>>();
+    for (i, header) in headers.iter().enumerate() {
+        let values = match header {
+            "timestamp" => rows.iter().map(|r| r.get(i).into()).collect(),
+            "name" | "health" | "schema_version" => continue,
+            _ => rows
+                .iter()
+                .map(|r| {
+                    r.get(i)
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .filter(|v| *v >= 0.0)
+                        .into()
+                })
+                .collect(),
+        };
+        columns.insert(header.to_string(), serde_json::Value::Array(values));
+    }
+
+    Ok(columns.into())
+}
+
+fn page(title: &str, refresh: bool, body: &str) -> String {
+    let refresh = match refresh {
+        true => format!("<meta http-equiv=\"refresh\" content=\"{}\">", REFRESH_SECS),
+        false => String::new(),
+    };
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">{}<title>{}</title>\
+         <style>{}</style></head><body><h1>{}</h1>{}</body></html>",
+        refresh,
+        escape(title),
+        STYLE,
+        escape(title),
+        body
+    )
+}
+
+/// Table of already escaped cells
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = String::from("<table><tr>");
+    for h in header {
+        out += &format!("<th>{}</th>", h);
+    }
+    out += "</tr>";
+    for row in rows {
+        out += "<tr>";
+        for cell in row {
+            out += &format!("<td>{}</td>", cell);
+        }
+        out += "</tr>";
+    }
+
+    out + "</table>"
+}
+
+fn link(url: &str, text: &str) -> String {
+    format!("<a href=\"{}\">{}</a>", escape(url), escape(text))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = crate::utils::test_dir("web_samples");
+        let csv_file = dir.join("monitor.csv");
+        fs::write(
+            &csv_file,
+            "name,timestamp,edge_covered,line_cov_percent,health,schema_version\n\
+             a,2024-01-31 09:35:00,800,-1,healthy,3\n\
+             a,2024-01-31 09:40:00,900,10.5,healthy,3\n",
+        )?;
+
+        let samples = samples(&csv_file.display().to_string())?;
+        assert_eq!(samples["timestamp"][1], "2024-01-31 09:40:00");
+        assert_eq!(samples["edge_covered"], serde_json::json!([800.0, 900.0]));
+        assert_eq!(samples["line_cov_percent"], serde_json::json!([null, 10.5]));
+        assert!(samples.get("health").is_none());
+        assert_eq!(escape("<a href=\"x\">"), "&lt;a href=&quot;x&quot;&gt;");
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}